@@ -0,0 +1,35 @@
+use crate::HardwareGroup;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// A condition worth calling out next to the readings, such as a throttling
+/// GPU. Alerts are recomputed on every sample.
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub group: HardwareGroup,
+    /// Matches the `device` of the sensors the alert is about.
+    pub device: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(
+        group: HardwareGroup,
+        device: impl Into<String>,
+        severity: Severity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            group,
+            device: device.into(),
+            severity,
+            message: message.into(),
+        }
+    }
+}
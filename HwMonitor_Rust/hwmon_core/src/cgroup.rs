@@ -0,0 +1,289 @@
+//! Per-cgroup resource use from the cgroup v2 hierarchy (`/sys/fs/cgroup`).
+//!
+//! Every cgroup directory has the same interface files: `cpu.stat`
+//! (cumulative `usage_usec`), `memory.current` and `memory.max`, `io.stat`
+//! (cumulative bytes per device), `pids.current` and the PSI files
+//! `{cpu,memory,io}.pressure`. Only systemd units and container scopes are
+//! reported; the cgroups in between are plumbing.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::psi::{read_pressure, RESOURCES};
+use crate::sysfs::{read_string, read_value};
+use crate::{HardwareGroup, Sensor, SensorKind, SensorSource, Snapshot};
+
+pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Deep enough for `user.slice/user-1000.slice/user@1000.service/app.slice/x.scope`.
+const MAX_DEPTH: usize = 6;
+
+/// Container engines' scope prefixes and how to name them.
+const CONTAINER_PREFIXES: &[(&str, &str)] = &[
+    ("docker-", "docker"),
+    ("libpod-conmon-", "podman conmon"),
+    ("libpod-", "podman"),
+    ("cri-containerd-", "containerd"),
+    ("crio-", "cri-o"),
+];
+
+/// A reported cgroup; its sensors use `path` as their `device`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cgroup {
+    /// Path below the cgroup root, e.g. `system.slice/docker-3f2a….scope`.
+    pub path: String,
+    /// Friendly name, e.g. `docker 3f2a1b9c0d4e` or `sshd service`.
+    pub name: String,
+    pub pids: Option<u64>,
+}
+
+/// Turns a cgroup directory name into something readable, e.g.
+/// `docker-3f2a….scope` into `docker 3f2a1b9c0d4e` and `nginx.service` into
+/// `nginx service`. systemd's `\x2d` escapes are undone.
+pub fn friendly_name(name: &str) -> String {
+    let name = name.replace("\\x2d", "-");
+    if let Some(stem) = name.strip_suffix(".scope") {
+        for (prefix, engine) in CONTAINER_PREFIXES {
+            if let Some(id) = stem.strip_prefix(prefix) {
+                return format!("{engine} {}", id.chars().take(12).collect::<String>());
+            }
+        }
+    }
+    for unit in ["service", "scope", "slice"] {
+        if let Some(stem) = name.strip_suffix(&format!(".{unit}")) {
+            return format!("{stem} {unit}");
+        }
+    }
+    // Docker's cgroupfs driver puts containers in `docker/<id>`.
+    if name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return format!("container {}", name.chars().take(12).collect::<String>());
+    }
+    name
+}
+
+fn is_reported(name: &str, parent: &str) -> bool {
+    name.ends_with(".slice")
+        || name.ends_with(".service")
+        || name.ends_with(".scope")
+        || matches!(parent, "docker" | "libpod_parent")
+}
+
+/// Sums the `rbytes` and `wbytes` of every device in `io.stat`.
+pub fn parse_io_stat(text: &str) -> (u64, u64) {
+    let mut read = 0;
+    let mut written = 0;
+    for field in text.split_whitespace() {
+        match field.split_once('=') {
+            Some(("rbytes", value)) => read += value.parse::<u64>().unwrap_or(0),
+            Some(("wbytes", value)) => written += value.parse::<u64>().unwrap_or(0),
+            _ => {}
+        }
+    }
+    (read, written)
+}
+
+/// Reads `usage_usec` from `cpu.stat`.
+pub fn parse_cpu_usage(text: &str) -> Option<u64> {
+    text.lines().find_map(|line| line.strip_prefix("usage_usec ")?.trim().parse().ok())
+}
+
+#[derive(Clone, Copy)]
+struct Counters {
+    cpu_usec: Option<u64>,
+    io: Option<(u64, u64)>,
+    read_at: Instant,
+}
+
+/// Publishes CPU, memory, I/O and pressure per systemd unit and container,
+/// and the cgroups themselves in [`Snapshot::cgroups`].
+pub struct CgroupSource {
+    root: PathBuf,
+    previous: HashMap<String, Counters>,
+}
+
+impl CgroupSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_CGROUP_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/fs/cgroup`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            previous: HashMap::new(),
+        }
+    }
+
+    /// Paths of the reported cgroups below the root, depth first.
+    pub fn cgroup_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        walk(&self.root, "", 0, &mut paths);
+        paths
+    }
+}
+
+impl Default for CgroupSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn walk(dir: &Path, relative: &str, depth: usize, paths: &mut Vec<String>) {
+    if depth == MAX_DEPTH {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut children: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    children.sort();
+
+    let parent = relative.rsplit('/').next().unwrap_or("");
+    for child in children {
+        let path = if relative.is_empty() { child.clone() } else { format!("{relative}/{child}") };
+        if is_reported(&child, parent) {
+            paths.push(path.clone());
+        }
+        walk(&dir.join(&child), &path, depth + 1, paths);
+    }
+}
+
+impl SensorSource for CgroupSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        let now = Instant::now();
+        let mut previous = std::mem::take(&mut self.previous);
+
+        for path in self.cgroup_paths() {
+            let dir = self.root.join(&path);
+            let name = friendly_name(path.rsplit('/').next().unwrap_or(&path));
+            let sensor = |field: &str, label: &str, kind: SensorKind, value: f64| {
+                Sensor::new(
+                    format!("cgroup/{path}/{field}"),
+                    HardwareGroup::Cgroups,
+                    path.as_str(),
+                    format!("{name} {label}"),
+                    kind,
+                    value,
+                )
+            };
+
+            let counters = Counters {
+                cpu_usec: read_string(dir.join("cpu.stat")).and_then(|text| parse_cpu_usage(&text)),
+                io: read_string(dir.join("io.stat")).map(|text| parse_io_stat(&text)),
+                read_at: now,
+            };
+            if let Some(before) = previous.remove(&path) {
+                let elapsed = now.duration_since(before.read_at).as_secs_f64();
+                if elapsed > 0.0 {
+                    if let (Some(usage), Some(usage_before)) = (counters.cpu_usec, before.cpu_usec) {
+                        let cpu = usage.saturating_sub(usage_before) as f64 / (elapsed * 1e6) * 100.0;
+                        snapshot.sensors.push(sensor("cpu", "CPU", SensorKind::Load, cpu));
+                    }
+                    if let (Some((read, written)), Some((read_before, written_before))) = (counters.io, before.io) {
+                        let read = read.saturating_sub(read_before) as f64 / elapsed;
+                        let written = written.saturating_sub(written_before) as f64 / elapsed;
+                        snapshot.sensors.push(sensor("io_read", "Read", SensorKind::Throughput, read));
+                        snapshot.sensors.push(sensor("io_write", "Write", SensorKind::Throughput, written));
+                    }
+                }
+            }
+            self.previous.insert(path.clone(), counters);
+
+            if let Some(memory) = read_value::<f64>(dir.join("memory.current")) {
+                // `memory.max` is the word `max` when unlimited.
+                let limit = read_value::<f64>(dir.join("memory.max"));
+                snapshot.sensors.push(sensor("memory", "Memory", SensorKind::Data, memory).with_max(limit));
+            }
+            for resource in RESOURCES {
+                if let Some(some) = read_pressure(dir.join(format!("{resource}.pressure"))).and_then(|p| p.some) {
+                    snapshot.sensors.push(sensor(
+                        &format!("pressure_{resource}"),
+                        &format!("{resource} pressure"),
+                        SensorKind::Load,
+                        some.avg10,
+                    ));
+                }
+            }
+
+            let pids = read_value::<u64>(dir.join("pids.current"));
+            if let Some(pids) = pids {
+                snapshot.sensors.push(sensor("pids", "Processes", SensorKind::Count, pids as f64));
+            }
+
+            snapshot.cgroups.push(Cgroup { pids, name, path });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    #[test]
+    fn friendly_names() {
+        assert_eq!(friendly_name("nginx.service"), "nginx service");
+        assert_eq!(friendly_name("user\\x2d1000.slice"), "user-1000 slice");
+        assert_eq!(
+            friendly_name("docker-3f2a1b9c0d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d2e3f4a5b6c7d8e9f0a.scope"),
+            "docker 3f2a1b9c0d4e"
+        );
+        // Not an id, but must not be cut inside a character either.
+        assert_eq!(friendly_name("libpod-ünïcödé-nämé.scope"), "podman ünïcödé-nämé");
+        assert_eq!(friendly_name("docker-äöü.scope"), "docker äöü");
+    }
+
+    #[test]
+    fn reads_a_fake_hierarchy() {
+        let root = tempfile::tempdir().unwrap();
+        let docker = "system.slice/docker-3f2a1b9c0d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d2e3f4a5b6c7d8e9f0a.scope";
+        let write = |usage: &str, rbytes: &str| {
+            write_tree(
+                root.path(),
+                &[
+                    ("cgroup.controllers", "cpu io memory pids\n"),
+                    ("init.scope/pids.current", "1\n"),
+                    ("system.slice/sshd.service/cpu.stat", &format!("usage_usec {usage}\nuser_usec 0\n")),
+                    ("system.slice/sshd.service/memory.current", "4194304\n"),
+                    ("system.slice/sshd.service/memory.max", "max\n"),
+                    ("system.slice/sshd.service/pids.current", "3\n"),
+                    (
+                        "system.slice/sshd.service/io.stat",
+                        &format!("8:0 rbytes={rbytes} wbytes=0 rios=1 wios=0\n259:0 rbytes=0 wbytes=0\n"),
+                    ),
+                    (
+                        "system.slice/sshd.service/memory.pressure",
+                        "some avg10=1.25 avg60=0.00 avg300=0.00 total=10\nfull avg10=0.00 avg60=0.00 avg300=0.00 total=0\n",
+                    ),
+                    (&format!("{docker}/pids.current"), "12\n"),
+                    ("system.slice/plumbing/not-reported/pids.current", "1\n"),
+                ],
+            );
+        };
+        let mut source = CgroupSource::with_root(root.path());
+        write("1000000", "0");
+        source.collect(&mut Snapshot::default());
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        write("1000000", "4096");
+        let mut snapshot = Snapshot::default();
+        source.collect(&mut snapshot);
+
+        let names: Vec<&str> = snapshot.cgroups.iter().map(|cgroup| cgroup.name.as_str()).collect();
+        assert_eq!(names, ["init scope", "system slice", "docker 3f2a1b9c0d4e", "sshd service"]);
+
+        let sensor = |id: &str| snapshot.sensor(&format!("cgroup/{id}").into()).unwrap();
+        assert_eq!(sensor(&format!("{docker}/pids")).value, 12.0);
+        assert_eq!(sensor("system.slice/sshd.service/pids").label, "sshd service Processes");
+        assert_eq!(sensor("system.slice/sshd.service/cpu").value, 0.0);
+        assert!(sensor("system.slice/sshd.service/io_read").value > 0.0);
+        assert_eq!(sensor("system.slice/sshd.service/memory").max, None);
+        assert_eq!(sensor("system.slice/sshd.service/pressure_memory").value, 1.25);
+    }
+}
@@ -0,0 +1,205 @@
+//! User configuration, read from a TOML file.
+//!
+//! Every key is optional; a missing file behaves like an empty one.
+//!
+//! ```toml
+//! [cpu]
+//! temperature_sensor = "hwmon/k10temp/0000:00:18.3/temp1"
+//!
+//! [fans.labels]
+//! "hwmon/nct6775/nct6775.656/fan2" = "CPU Fan"
+//!
+//! [fans]
+//! stall_temperature = 80.0
+//!
+//! [fans.temperatures]
+//! "hwmon/nct6775/nct6775.656/fan2" = "hwmon/k10temp/0000:00:18.3/temp1"
+//!
+//! [gpu]
+//! nvidia_smi = "/usr/bin/nvidia-smi"
+//!
+//! [network]
+//! show_virtual = true
+//! ignore = ["veth*", "docker0"]
+//!
+//! [pressure]
+//! warning = 10.0
+//! critical = 40.0
+//!
+//! [power.multipliers]
+//! "hwmon/nct6775/nct6775.656" = 1.0
+//! "hwmon/nct6775/nct6775.656/in4" = 12.0
+//!
+//! [storage]
+//! smartctl = "/usr/sbin/smartctl"
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::fans::DEFAULT_STALL_TEMPERATURE;
+use crate::network::InterfaceFilter;
+use crate::{psi, SensorId};
+
+/// Environment variable that overrides the config file location.
+pub const CONFIG_ENV: &str = "HWMON_CONFIG";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub cpu: CpuConfig,
+    pub fans: FanConfig,
+    pub gpu: GpuConfig,
+    pub network: NetworkConfig,
+    pub power: PowerConfig,
+    pub pressure: PressureConfig,
+    pub storage: StorageConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CpuConfig {
+    /// Sensor to report as the CPU temperature instead of guessing one.
+    pub temperature_sensor: Option<SensorId>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FanConfig {
+    /// Names for fan headers, by fan sensor id.
+    pub labels: HashMap<SensorId, String>,
+    /// Temperature each fan cools, by fan sensor id, for stall detection.
+    pub temperatures: HashMap<SensorId, SensorId>,
+    /// Temperature in °C at which a fan reading 0 RPM counts as stalled.
+    pub stall_temperature: f64,
+}
+
+impl Default for FanConfig {
+    fn default() -> Self {
+        Self {
+            labels: HashMap::new(),
+            temperatures: HashMap::new(),
+            stall_temperature: DEFAULT_STALL_TEMPERATURE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GpuConfig {
+    /// nvidia-smi binary to run; looked up on `PATH` when unset.
+    pub nvidia_smi: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub show_loopback: bool,
+    /// Show veth pairs, bridges, tunnels and other interfaces without hardware.
+    pub show_virtual: bool,
+    /// Interface names to hide; a trailing `*` matches any suffix.
+    pub ignore: Vec<String>,
+}
+
+impl NetworkConfig {
+    pub fn filter(&self) -> InterfaceFilter {
+        InterfaceFilter {
+            show_loopback: self.show_loopback,
+            show_virtual: self.show_virtual,
+            ignore: self.ignore.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    /// Factors applied to raw rail readings, by chip id prefix or sensor id;
+    /// a sensor id takes precedence over its chip.
+    pub multipliers: HashMap<String, f64>,
+}
+
+/// Limits for the share of the last 10 s in which every task waited on a
+/// resource (PSI `full avg10`), in percent.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PressureConfig {
+    pub warning: f64,
+    pub critical: f64,
+}
+
+impl Default for PressureConfig {
+    fn default() -> Self {
+        Self {
+            warning: psi::DEFAULT_WARNING,
+            critical: psi::DEFAULT_CRITICAL,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// smartctl binary to run; looked up on `PATH` when unset.
+    pub smartctl: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Loads the file at [`Config::default_path`], or the defaults if there is none.
+    pub fn load_default() -> Result<Self, ConfigError> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// `$HWMON_CONFIG`, else `hwmon/config.toml` under the platform config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+
+        let config_dir = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        };
+        config_dir.map(|dir| dir.join("hwmon").join("config.toml"))
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "could not read {}: {e}", path.display()),
+            ConfigError::Parse(path, e) => write!(f, "invalid config in {}: {e}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(_, e) => Some(e),
+            ConfigError::Parse(_, e) => Some(e),
+        }
+    }
+}
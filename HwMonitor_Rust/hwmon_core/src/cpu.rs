@@ -0,0 +1,257 @@
+//! Per-CPU topology and frequency from `/sys/devices/system/cpu`.
+//!
+//! Each online logical CPU has a `topology` directory naming its physical
+//! package and core, and, when a cpufreq driver is loaded, a `cpufreq`
+//! directory with the current frequency, its limits and the governor.
+//! Frequencies there are in kHz. On x86, `thermal_throttle` counts how often
+//! each core and its package have been throttled for heat since boot.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::sysfs::{indexed_entries, read_string, read_value};
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_CPU_ROOT: &str = "/sys/devices/system/cpu";
+
+/// How long a throttle alert stays up after the last counter increase;
+/// throttling comes in short bursts that would otherwise flicker.
+const THROTTLE_HOLD: Duration = Duration::from_secs(5);
+
+/// One logical CPU (hardware thread); frequencies are in MHz.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicalCpu {
+    pub index: u32,
+    pub package: u32,
+    /// Core id within the package; SMT siblings share it.
+    pub core: u32,
+    pub frequency: Option<f64>,
+    pub min_frequency: Option<f64>,
+    pub max_frequency: Option<f64>,
+    pub governor: Option<String>,
+    pub core_throttle_count: Option<u64>,
+    /// Shared by every CPU in the package.
+    pub package_throttle_count: Option<u64>,
+}
+
+impl LogicalCpu {
+    /// Kernel name such as `cpu3`, used as the `device` of its sensors.
+    pub fn name(&self) -> String {
+        format!("cpu{}", self.index)
+    }
+}
+
+/// Throttle counters of one CPU and when each last went up.
+#[derive(Default)]
+struct ThrottleState {
+    core_count: Option<u64>,
+    package_count: Option<u64>,
+    core_throttled_at: Option<Instant>,
+    package_throttled_at: Option<Instant>,
+}
+
+/// Publishes per-CPU frequencies and the topology in [`Snapshot::cpus`], and
+/// raises an alert while throttle counters are going up.
+pub struct CpuSource {
+    root: PathBuf,
+    /// By CPU index.
+    throttle: HashMap<u32, ThrottleState>,
+}
+
+impl CpuSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_CPU_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/devices/system/cpu`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            throttle: HashMap::new(),
+        }
+    }
+
+    /// Online CPUs in index order; offline ones have no topology.
+    pub fn cpus(&self) -> Vec<LogicalCpu> {
+        indexed_entries(&self.root, "cpu")
+            .into_iter()
+            .filter_map(|(index, path)| {
+                let khz = |name: &str| read_value::<f64>(path.join("cpufreq").join(name)).map(|khz| khz / 1000.0);
+                Some(LogicalCpu {
+                    index,
+                    package: read_value(path.join("topology/physical_package_id"))?,
+                    core: read_value(path.join("topology/core_id"))?,
+                    frequency: khz("scaling_cur_freq"),
+                    min_frequency: khz("scaling_min_freq"),
+                    max_frequency: khz("scaling_max_freq"),
+                    governor: read_string(path.join("cpufreq/scaling_governor")),
+                    core_throttle_count: read_value(path.join("thermal_throttle/core_throttle_count")),
+                    package_throttle_count: read_value(path.join("thermal_throttle/package_throttle_count")),
+                })
+            })
+            .collect()
+    }
+
+    /// One alert per package whose package or core counters went up within
+    /// [`THROTTLE_HOLD`] of `now`.
+    fn throttle_alerts(&mut self, cpus: &[LogicalCpu], now: Instant) -> Vec<Alert> {
+        let increased = |now: Option<u64>, before: Option<u64>| matches!((now, before), (Some(now), Some(before)) if now > before);
+        let recent = |at: Option<Instant>| at.is_some_and(|at| now.duration_since(at) < THROTTLE_HOLD);
+
+        let mut packages: Vec<(u32, bool, Vec<u32>)> = Vec::new();
+        for cpu in cpus {
+            let state = self.throttle.entry(cpu.index).or_default();
+            if increased(cpu.core_throttle_count, state.core_count) {
+                state.core_throttled_at = Some(now);
+            }
+            if increased(cpu.package_throttle_count, state.package_count) {
+                state.package_throttled_at = Some(now);
+            }
+            state.core_count = cpu.core_throttle_count;
+            state.package_count = cpu.package_throttle_count;
+            let package_throttled = recent(state.package_throttled_at);
+            let core_throttled = recent(state.core_throttled_at);
+
+            let entry = match packages.iter_mut().find(|(package, _, _)| *package == cpu.package) {
+                Some(entry) => entry,
+                None => {
+                    packages.push((cpu.package, false, Vec::new()));
+                    packages.last_mut().unwrap()
+                }
+            };
+            entry.1 |= package_throttled;
+            if core_throttled && !entry.2.contains(&cpu.core) {
+                entry.2.push(cpu.core);
+            }
+        }
+
+        packages
+            .into_iter()
+            .filter(|(_, package_throttled, cores)| *package_throttled || !cores.is_empty())
+            .map(|(package, _, mut cores)| {
+                cores.sort();
+                let mut message = format!("Package {package} is thermal throttling");
+                if !cores.is_empty() {
+                    let cores: Vec<String> = cores.iter().map(u32::to_string).collect();
+                    let noun = if cores.len() == 1 { "core" } else { "cores" };
+                    message += &format!(" ({noun} {})", cores.join(", "));
+                }
+                Alert::new(HardwareGroup::Cpu, "thermal_throttle", Severity::Warning, message)
+            })
+            .collect()
+    }
+}
+
+impl Default for CpuSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for CpuSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        let cpus = self.cpus();
+        for cpu in &cpus {
+            if let Some(frequency) = cpu.frequency {
+                snapshot.sensors.push(
+                    Sensor::new(
+                        format!("cpu/{}/frequency", cpu.name()),
+                        HardwareGroup::Cpu,
+                        cpu.name(),
+                        format!("CPU {} Frequency", cpu.index),
+                        SensorKind::Clock,
+                        frequency,
+                    )
+                    .with_min(cpu.min_frequency)
+                    .with_max(cpu.max_frequency),
+                );
+            }
+        }
+        snapshot.alerts.extend(self.throttle_alerts(&cpus, Instant::now()));
+        snapshot.cpus = cpus;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    #[test]
+    fn reads_topology_and_frequencies() {
+        let root = tempfile::tempdir().unwrap();
+        let mut files = Vec::new();
+        // Two cores with two SMT siblings each; cpu4 is offline.
+        for (cpu, core) in [(0, 0), (1, 1), (2, 0), (3, 1)] {
+            files.push((format!("cpu{cpu}/topology/physical_package_id"), "0\n".to_string()));
+            files.push((format!("cpu{cpu}/topology/core_id"), format!("{core}\n")));
+            files.push((format!("cpu{cpu}/cpufreq/scaling_cur_freq"), format!("{}\n", 3_400_000 + cpu * 100_000)));
+            files.push((format!("cpu{cpu}/cpufreq/scaling_min_freq"), "550000\n".to_string()));
+            files.push((format!("cpu{cpu}/cpufreq/scaling_max_freq"), "4850000\n".to_string()));
+            files.push((format!("cpu{cpu}/cpufreq/scaling_governor"), "schedutil\n".to_string()));
+        }
+        files.push(("cpu4/online".to_string(), "0\n".to_string()));
+        files.push(("cpufreq/policy0/scaling_governor".to_string(), "schedutil\n".to_string()));
+        files.push(("cpuidle/current_driver".to_string(), "intel_idle\n".to_string()));
+        let files: Vec<(&str, &str)> = files.iter().map(|(path, text)| (path.as_str(), text.as_str())).collect();
+        write_tree(root.path(), &files);
+
+        let mut source = CpuSource::with_root(root.path());
+        let cpus = source.cpus();
+        let topology: Vec<(u32, u32, u32)> = cpus.iter().map(|cpu| (cpu.index, cpu.package, cpu.core)).collect();
+        assert_eq!(topology, [(0, 0, 0), (1, 0, 1), (2, 0, 0), (3, 0, 1)]);
+        assert_eq!(
+            cpus[2],
+            LogicalCpu {
+                index: 2,
+                package: 0,
+                core: 0,
+                frequency: Some(3600.0),
+                min_frequency: Some(550.0),
+                max_frequency: Some(4850.0),
+                governor: Some("schedutil".to_string()),
+                core_throttle_count: None,
+                package_throttle_count: None,
+            }
+        );
+
+        let mut snapshot = Snapshot::default();
+        source.collect(&mut snapshot);
+        let frequency = snapshot.sensor(&"cpu/cpu3/frequency".into()).unwrap();
+        assert_eq!((frequency.value, frequency.min, frequency.max), (3700.0, Some(550.0), Some(4850.0)));
+        assert_eq!(frequency.label, "CPU 3 Frequency");
+        assert_eq!(snapshot.cpus.len(), 4);
+    }
+
+    #[test]
+    fn throttle_alert_outlasts_the_increase() {
+        let root = tempfile::tempdir().unwrap();
+        let counts = |core: &str, package: &str| {
+            write_tree(
+                root.path(),
+                &[
+                    ("cpu0/topology/physical_package_id", "0\n"),
+                    ("cpu0/topology/core_id", "2\n"),
+                    ("cpu0/thermal_throttle/core_throttle_count", core),
+                    ("cpu0/thermal_throttle/package_throttle_count", package),
+                ],
+            );
+        };
+        let mut source = CpuSource::with_root(root.path());
+        let start = Instant::now();
+        let messages = |source: &mut CpuSource, after: Duration| -> Vec<String> {
+            let cpus = source.cpus();
+            source.throttle_alerts(&cpus, start + after).into_iter().map(|alert| alert.message).collect()
+        };
+
+        counts("10\n", "4\n");
+        assert!(messages(&mut source, Duration::ZERO).is_empty());
+
+        counts("11\n", "4\n");
+        let throttling = ["Package 0 is thermal throttling (core 2)"];
+        assert_eq!(messages(&mut source, Duration::from_secs(1)), throttling);
+        assert_eq!(messages(&mut source, Duration::from_secs(3)), throttling);
+        assert!(messages(&mut source, Duration::from_secs(1) + THROTTLE_HOLD).is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! Picks the one temperature that best represents "the CPU".
+//!
+//! Drivers disagree on naming: k10temp and zenpower report `Tctl`/`Tdie`/
+//! `TccdN`, coretemp reports `Package id N` and `Core N`, ARM boards expose a
+//! single `cpu_thermal` zone, and ACPI's `acpitz` is often the only thing left
+//! on older machines. Rules are tried in order and the first one matching any
+//! sensor wins; among several matches (multi-socket, multiple CCDs) the
+//! hottest is used.
+
+use crate::{Sensor, SensorId, SensorKind, SensorSource, Snapshot};
+
+/// The sensor chosen as the CPU temperature and why.
+#[derive(Debug, Clone)]
+pub struct CpuTemperature {
+    pub sensor: SensorId,
+    /// Which rule picked it, for showing next to the value.
+    pub reason: &'static str,
+}
+
+/// `(driver, label test, description)`; an empty driver matches any device.
+type Rule = (&'static str, fn(&str) -> bool, &'static str);
+
+const RULES: &[Rule] = &[
+    ("k10temp", |label| label == "Tdie", "k10temp Tdie"),
+    ("k10temp", |label| label == "Tctl", "k10temp Tctl"),
+    ("k10temp", |label| label.starts_with("Tccd"), "k10temp hottest CCD"),
+    ("zenpower", |label| label == "Tdie", "zenpower Tdie"),
+    ("zenpower", |label| label == "Tctl", "zenpower Tctl"),
+    ("zenpower", |label| label.starts_with("Tccd"), "zenpower hottest CCD"),
+    ("coretemp", |label| label.starts_with("Package id"), "coretemp package"),
+    ("coretemp", |label| label.starts_with("Core "), "coretemp hottest core"),
+    ("cpu_thermal", |_| true, "cpu_thermal zone"),
+    ("acpitz", |_| true, "ACPI thermal zone"),
+    // Platforms without hwmon only have sysinfo's component labels to go on.
+    ("", |label| label.to_lowercase().contains("cpu"), "component labelled CPU"),
+];
+
+/// Chooses the CPU temperature sensor; `pinned` wins whenever it is present.
+pub fn select_cpu_temperature(sensors: &[Sensor], pinned: Option<&SensorId>) -> Option<CpuTemperature> {
+    if let Some(pinned) = pinned {
+        if sensors.iter().any(|sensor| &sensor.id == pinned) {
+            return Some(CpuTemperature {
+                sensor: pinned.clone(),
+                reason: "pinned in config",
+            });
+        }
+    }
+
+    RULES.iter().find_map(|(driver, matches, reason)| {
+        sensors
+            .iter()
+            .filter(|sensor| sensor.kind == SensorKind::Temperature && sensor.value > 0.0)
+            .filter(|sensor| driver.is_empty() || sensor.device == *driver)
+            .filter(|sensor| matches(&sensor.label))
+            .max_by(|a, b| a.value.total_cmp(&b.value))
+            .map(|sensor| CpuTemperature {
+                sensor: sensor.id.clone(),
+                reason,
+            })
+    })
+}
+
+/// Fills in [`Snapshot::cpu_temperature`]; must run after the sources it picks from.
+pub struct CpuTempSelector {
+    pinned: Option<SensorId>,
+}
+
+impl CpuTempSelector {
+    pub fn new(pinned: Option<SensorId>) -> Self {
+        Self { pinned }
+    }
+}
+
+impl SensorSource for CpuTempSelector {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        snapshot.cpu_temperature = select_cpu_temperature(&snapshot.sensors, self.pinned.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HardwareGroup;
+
+    fn temp(driver: &str, attribute: &str, label: &str, celsius: f64) -> Sensor {
+        Sensor::new(
+            format!("hwmon/{driver}/{attribute}"),
+            HardwareGroup::Cpu,
+            driver,
+            label,
+            SensorKind::Temperature,
+            celsius,
+        )
+    }
+
+    fn selected(sensors: &[Sensor], pinned: Option<&str>) -> (String, &'static str) {
+        let pinned = pinned.map(SensorId::from);
+        let choice = select_cpu_temperature(sensors, pinned.as_ref()).unwrap();
+        (choice.sensor.as_str().to_string(), choice.reason)
+    }
+
+    #[test]
+    fn prefers_k10temp_over_acpitz() {
+        let sensors = [
+            temp("acpitz", "temp1", "temp1", 90.0),
+            temp("k10temp", "temp1", "Tctl", 55.0),
+            temp("k10temp", "temp2", "Tdie", 45.0),
+        ];
+        assert_eq!(selected(&sensors, None), ("hwmon/k10temp/temp2".to_string(), "k10temp Tdie"));
+        assert_eq!(selected(&sensors[..2], None), ("hwmon/k10temp/temp1".to_string(), "k10temp Tctl"));
+    }
+
+    #[test]
+    fn picks_the_hottest_ccd() {
+        let sensors = [
+            temp("k10temp", "temp3", "Tccd1", 61.0),
+            temp("k10temp", "temp4", "Tccd2", 68.5),
+            temp("acpitz", "temp1", "temp1", 70.0),
+        ];
+        assert_eq!(selected(&sensors, None), ("hwmon/k10temp/temp4".to_string(), "k10temp hottest CCD"));
+    }
+
+    #[test]
+    fn prefers_the_coretemp_package_over_cores() {
+        let sensors = [
+            temp("coretemp", "temp2", "Core 0", 72.0),
+            temp("coretemp", "temp1", "Package id 0", 65.0),
+            temp("coretemp", "temp3", "Core 1", 70.0),
+        ];
+        assert_eq!(selected(&sensors, None), ("hwmon/coretemp/temp1".to_string(), "coretemp package"));
+    }
+
+    #[test]
+    fn pinned_sensor_wins_while_present() {
+        let sensors = [temp("k10temp", "temp1", "Tctl", 55.0), temp("nct6775", "temp2", "CPUTIN", 48.0)];
+        assert_eq!(
+            selected(&sensors, Some("hwmon/nct6775/temp2")),
+            ("hwmon/nct6775/temp2".to_string(), "pinned in config")
+        );
+        assert_eq!(
+            selected(&sensors, Some("hwmon/nct6775/temp9")),
+            ("hwmon/k10temp/temp1".to_string(), "k10temp Tctl")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Block device I/O counters from `/proc/diskstats`.
+//!
+//! Each line is `major minor name` followed by cumulative counters; see the
+//! kernel's `Documentation/admin-guide/iostats.rst`. Sectors are always 512
+//! bytes here, and times are in milliseconds.
+
+use std::time::Duration;
+
+pub const DEFAULT_DISKSTATS: &str = "/proc/diskstats";
+
+const SECTOR_SIZE: f64 = 512.0;
+
+/// The counters of one line that the rates are built from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskStats {
+    pub reads: u64,
+    pub sectors_read: u64,
+    pub read_ms: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    pub write_ms: u64,
+    /// Time with at least one request in flight.
+    pub busy_ms: u64,
+}
+
+/// Parses `/proc/diskstats` into counters by device name.
+pub fn parse_diskstats(text: &str) -> Vec<(String, DiskStats)> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let counter = |i: usize| fields.get(i)?.parse::<u64>().ok();
+            let stats = DiskStats {
+                reads: counter(3)?,
+                sectors_read: counter(5)?,
+                read_ms: counter(6)?,
+                writes: counter(7)?,
+                sectors_written: counter(9)?,
+                write_ms: counter(10)?,
+                busy_ms: counter(12)?,
+            };
+            Some((fields[2].to_string(), stats))
+        })
+        .collect()
+}
+
+/// Rates over one sample interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiskIo {
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    /// Mean time a completed request took, queueing included; `None` when
+    /// nothing completed.
+    pub latency_ms: Option<f64>,
+    /// Share of the interval the device was busy, in percent.
+    pub utilization: f64,
+}
+
+impl DiskIo {
+    /// Rates between two readings `elapsed` apart. Counters go backwards when
+    /// a device is removed and re-added, and wrap at 2^32 on 32-bit kernels;
+    /// either counts as no activity for that interval.
+    pub fn between(previous: &DiskStats, current: &DiskStats, elapsed: Duration) -> Option<Self> {
+        let seconds = elapsed.as_secs_f64();
+        if seconds <= 0.0 {
+            return None;
+        }
+        let delta = |now: u64, before: u64| now.saturating_sub(before) as f64;
+        let reads = delta(current.reads, previous.reads);
+        let writes = delta(current.writes, previous.writes);
+        let request_ms = delta(current.read_ms, previous.read_ms) + delta(current.write_ms, previous.write_ms);
+
+        Some(Self {
+            read_bytes_per_sec: delta(current.sectors_read, previous.sectors_read) * SECTOR_SIZE / seconds,
+            write_bytes_per_sec: delta(current.sectors_written, previous.sectors_written) * SECTOR_SIZE / seconds,
+            read_iops: reads / seconds,
+            write_iops: writes / seconds,
+            latency_ms: (reads + writes > 0.0).then(|| request_ms / (reads + writes)),
+            utilization: (delta(current.busy_ms, previous.busy_ms) / (seconds * 1000.0) * 100.0).min(100.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATS: DiskStats = DiskStats {
+        reads: 1000,
+        sectors_read: 20_000,
+        read_ms: 3000,
+        writes: 500,
+        sectors_written: 8000,
+        write_ms: 1500,
+        busy_ms: 4000,
+    };
+
+    #[test]
+    fn parses_every_column_layout() {
+        // Before 4.18, with discards (4.18) and with flushes (5.5).
+        let text = "\
+   8       0 sda 1000 10 20000 3000 500 20 8000 1500 0 4000 4500
+ 259       0 nvme0n1 1000 10 20000 3000 500 20 8000 1500 0 4000 4500 7 0 56 2
+ 259       1 nvme0n1p1 1000 10 20000 3000 500 20 8000 1500 0 4000 4500 7 0 56 2 30 9
+   7       0 loop0 bad line
+";
+        let stats = parse_diskstats(text);
+        let names: Vec<&str> = stats.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["sda", "nvme0n1", "nvme0n1p1"]);
+        assert!(stats.iter().all(|(_, stats)| *stats == STATS));
+    }
+
+    #[test]
+    fn rates_over_one_interval() {
+        let current = DiskStats {
+            reads: 1100,
+            sectors_read: 22_048,
+            read_ms: 3150,
+            writes: 550,
+            sectors_written: 9024,
+            write_ms: 1650,
+            busy_ms: 4500,
+        };
+        let io = DiskIo::between(&STATS, &current, Duration::from_secs(2)).unwrap();
+        assert_eq!(io.read_bytes_per_sec, 2048.0 * 512.0 / 2.0);
+        assert_eq!(io.write_bytes_per_sec, 1024.0 * 512.0 / 2.0);
+        assert_eq!((io.read_iops, io.write_iops), (50.0, 25.0));
+        assert_eq!(io.latency_ms, Some(300.0 / 150.0));
+        assert_eq!(io.utilization, 25.0);
+    }
+
+    #[test]
+    fn idle_device_has_no_latency() {
+        let io = DiskIo::between(&STATS, &STATS, Duration::from_secs(1)).unwrap();
+        assert_eq!(io.latency_ms, None);
+        assert_eq!(io.utilization, 0.0);
+    }
+
+    #[test]
+    fn utilization_is_capped() {
+        // busy_ms is sampled at a different moment than the interval is timed.
+        let current = DiskStats {
+            busy_ms: STATS.busy_ms + 1010,
+            ..STATS
+        };
+        assert_eq!(DiskIo::between(&STATS, &current, Duration::from_secs(1)).unwrap().utilization, 100.0);
+    }
+
+    #[test]
+    fn wrapped_or_reset_counters_count_as_idle() {
+        let reset = DiskStats {
+            reads: 3,
+            sectors_read: 24,
+            read_ms: 1,
+            ..DiskStats::default()
+        };
+        let io = DiskIo::between(&STATS, &reset, Duration::from_secs(1)).unwrap();
+        assert_eq!(io, DiskIo::default());
+        assert_eq!(DiskIo::between(&STATS, &STATS, Duration::ZERO), None);
+    }
+}
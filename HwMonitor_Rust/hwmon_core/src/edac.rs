@@ -0,0 +1,178 @@
+//! ECC memory error counters from EDAC (`/sys/devices/system/edac/mc`).
+//!
+//! Each memory controller `mcN` counts corrected (`ce_count`) and
+//! uncorrected (`ue_count`) errors since the driver loaded. Newer drivers
+//! break them down per `dimmN` (`dimm_ce_count`, `dimm_label`), older ones per
+//! chip-select row `csrowN` (`ce_count`, `ch0_dimm_label`).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::sysfs::{numbered_entries, read_string, read_value};
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_EDAC_ROOT: &str = "/sys/devices/system/edac/mc";
+
+/// Error counts of a controller or of one DIMM/csrow on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdacCounts {
+    /// Id part such as `mc0` or `mc0/dimm3`.
+    pub id: String,
+    /// `mc0`, or the firmware's DIMM label such as `CPU_SrcID#0_Ha#0_Chan#1_DIMM#0`.
+    pub label: String,
+    pub corrected: u64,
+    pub uncorrected: u64,
+}
+
+fn read_counts(path: &Path, id: String, label: String, prefix: &str) -> Option<EdacCounts> {
+    Some(EdacCounts {
+        corrected: read_value(path.join(format!("{prefix}ce_count")))?,
+        uncorrected: read_value(path.join(format!("{prefix}ue_count")))?,
+        id,
+        label,
+    })
+}
+
+/// Counts for every controller followed by its DIMMs (or csrows).
+pub fn read_edac(root: &Path) -> Vec<EdacCounts> {
+    let mut counts = Vec::new();
+    for controller in numbered_entries(root, "mc") {
+        let Some(mc) = controller.file_name().and_then(|name| name.to_str()).map(str::to_string) else {
+            continue;
+        };
+        counts.extend(read_counts(&controller, mc.clone(), mc.clone(), ""));
+
+        let dimms = numbered_entries(&controller, "dimm");
+        let (parts, prefix, label_file) = if dimms.is_empty() {
+            (numbered_entries(&controller, "csrow"), "", "ch0_dimm_label")
+        } else {
+            (dimms, "dimm_", "dimm_label")
+        };
+        for part in parts {
+            let Some(name) = part.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let label = read_string(part.join(label_file))
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| format!("{mc} {name}"));
+            counts.extend(read_counts(&part, format!("{mc}/{name}"), label, prefix));
+        }
+    }
+    counts
+}
+
+/// Publishes error counts in the Memory group and keeps alerting about any
+/// errors that appeared after monitoring started.
+pub struct EdacSource {
+    root: PathBuf,
+    /// Counts from the first sample, by id.
+    baseline: HashMap<String, (u64, u64)>,
+}
+
+impl EdacSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_EDAC_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/devices/system/edac/mc`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            baseline: HashMap::new(),
+        }
+    }
+}
+
+impl Default for EdacSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for EdacSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        let all = read_edac(&self.root);
+        for counts in &all {
+            let sensor = |field: &str, label: &str, value: u64| {
+                Sensor::new(
+                    format!("edac/{}/{field}", counts.id),
+                    HardwareGroup::Memory,
+                    "edac",
+                    format!("{} {label}", counts.label),
+                    SensorKind::Count,
+                    value as f64,
+                )
+            };
+            snapshot.sensors.push(sensor("corrected", "Corrected Errors", counts.corrected));
+            snapshot.sensors.push(sensor("uncorrected", "Uncorrected Errors", counts.uncorrected));
+
+            // Only DIMMs alert when the controller has them, so errors are not reported twice.
+            let is_controller = !counts.id.contains('/');
+            let has_parts = is_controller && all.iter().any(|part| part.id.starts_with(&format!("{}/", counts.id)));
+            let (corrected_before, uncorrected_before) =
+                *self.baseline.entry(counts.id.clone()).or_insert((counts.corrected, counts.uncorrected));
+            if has_parts {
+                continue;
+            }
+
+            let new_uncorrected = counts.uncorrected.saturating_sub(uncorrected_before);
+            let new_corrected = counts.corrected.saturating_sub(corrected_before);
+            if new_uncorrected > 0 {
+                snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Memory,
+                    "edac",
+                    Severity::Critical,
+                    format!("{new_uncorrected} uncorrected memory errors on {} since monitoring started", counts.label),
+                ));
+            }
+            if new_corrected > 0 {
+                snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Memory,
+                    "edac",
+                    Severity::Warning,
+                    format!("{new_corrected} corrected memory errors on {} since monitoring started", counts.label),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    #[test]
+    fn alerts_on_new_errors_per_dimm() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |corrected: &str| {
+            write_tree(
+                root.path(),
+                &[
+                    ("mc0/ce_count", corrected),
+                    ("mc0/ue_count", "0\n"),
+                    ("mc0/dimm0/dimm_ce_count", corrected),
+                    ("mc0/dimm0/dimm_ue_count", "0\n"),
+                    ("mc0/dimm0/dimm_label", "DIMM_A1\n"),
+                    ("mc0/dimm1/dimm_ce_count", "0\n"),
+                    ("mc0/dimm1/dimm_ue_count", "0\n"),
+                ],
+            );
+        };
+        let mut source = EdacSource::with_root(root.path());
+        write("2\n");
+        let mut first = Snapshot::default();
+        source.collect(&mut first);
+        assert!(first.alerts.is_empty());
+
+        write("5\n");
+        let mut snapshot = Snapshot::default();
+        source.collect(&mut snapshot);
+
+        let messages: Vec<&str> = snapshot.alerts.iter().map(|alert| alert.message.as_str()).collect();
+        assert_eq!(messages, ["3 corrected memory errors on DIMM_A1 since monitoring started"]);
+        assert_eq!(snapshot.alerts[0].severity, Severity::Warning);
+        assert_eq!(snapshot.sensor(&"edac/mc0/dimm0/corrected".into()).unwrap().value, 5.0);
+        assert_eq!(snapshot.sensor(&"edac/mc0/dimm1/corrected".into()).unwrap().label, "mc0 dimm1 Corrected Errors");
+    }
+}
@@ -0,0 +1,222 @@
+//! Fan stall detection.
+//!
+//! A fan reading 0 RPM is normal for empty headers and for fans whose curve
+//! stops them at idle (amdgpu's zero-RPM mode, many board fan curves). It is
+//! only a stall when something wants the fan to turn: the controller sets a
+//! speed target (`fanN_target`) or a PWM duty cycle above zero, or the
+//! temperature the fan cools is above its `max` or the configured stall
+//! temperature. The condition has to hold for a few samples in a row, since
+//! a stopped fan takes a moment to spin up once the controller asks it to.
+
+use std::collections::HashMap;
+
+use crate::{Alert, Sensor, SensorId, SensorKind, SensorSource, Severity, Snapshot, Unit};
+
+/// Temperature above which a fan reading 0 RPM counts as stalled, in °C,
+/// unless configured otherwise.
+pub const DEFAULT_STALL_TEMPERATURE: f64 = 80.0;
+
+/// Consecutive samples a fan has to look stalled before it is reported.
+const STALL_SAMPLES: u32 = 2;
+
+/// Raises an alert for stalled fans; runs after the sources and
+/// [`CpuTempSelector`](crate::CpuTempSelector).
+pub struct FanStallDetector {
+    /// Fan sensor id to the temperature it cools, from config.
+    temperatures: HashMap<SensorId, SensorId>,
+    stall_temperature: f64,
+    /// Samples in a row each fan has looked stalled, by fan sensor id.
+    stalled_for: HashMap<SensorId, u32>,
+}
+
+impl FanStallDetector {
+    pub fn new(temperatures: HashMap<SensorId, SensorId>) -> Self {
+        Self {
+            temperatures,
+            stall_temperature: DEFAULT_STALL_TEMPERATURE,
+            stalled_for: HashMap::new(),
+        }
+    }
+
+    /// Treats a stopped fan as stalled once its temperature reaches `celsius`.
+    pub fn with_stall_temperature(mut self, celsius: f64) -> Self {
+        self.stall_temperature = celsius;
+        self
+    }
+
+    /// The configured temperature for `fan`, else the hottest temperature on
+    /// the same device, else the CPU temperature.
+    fn associated_temperature<'a>(&self, fan: &Sensor, snapshot: &'a Snapshot) -> Option<&'a Sensor> {
+        if let Some(id) = self.temperatures.get(&fan.id) {
+            return snapshot.sensor(id);
+        }
+        snapshot
+            .sensors
+            .iter()
+            .filter(|sensor| sensor.kind == SensorKind::Temperature && sensor.device == fan.device)
+            .max_by(|a, b| a.value.total_cmp(&b.value))
+            .or_else(|| snapshot.cpu_temperature())
+    }
+
+    /// Why a fan reading 0 RPM should be turning, if anything says it should.
+    fn demand(&self, fan: &Sensor, snapshot: &Snapshot) -> Option<String> {
+        let related = |suffix: &str| {
+            let id = SensorId::from(format!("{}_{suffix}", fan.id.as_str()));
+            snapshot.sensor(&id).filter(|sensor| sensor.value > 0.0)
+        };
+        if let Some(target) = related("target") {
+            return Some(format!("the controller asks for {}", target.display_value()));
+        }
+        if let Some(pwm) = related("pwm") {
+            return Some(format!("it is driven at {} duty", pwm.display_value()));
+        }
+
+        let temperature = self.associated_temperature(fan, snapshot)?;
+        let limit = match temperature.max {
+            Some(max) if max > 0.0 => max.min(self.stall_temperature),
+            _ => self.stall_temperature,
+        };
+        (temperature.value >= limit).then(|| format!("{} is at {}", temperature.label, temperature.display_value()))
+    }
+}
+
+impl SensorSource for FanStallDetector {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        // Targets share the fan kind but are set points, not readings.
+        let fans: Vec<&Sensor> = snapshot
+            .sensors
+            .iter()
+            .filter(|s| s.kind == SensorKind::Fan && s.unit == Unit::Rpm && !s.id.as_str().ends_with("_target"))
+            .collect();
+
+        let mut stalled_for = HashMap::new();
+        let mut alerts = Vec::new();
+        for fan in fans {
+            let Some(demand) = (fan.value <= 0.0).then(|| self.demand(fan, snapshot)).flatten() else {
+                continue;
+            };
+            let samples = self.stalled_for.get(&fan.id).copied().unwrap_or(0) + 1;
+            stalled_for.insert(fan.id.clone(), samples);
+            if samples >= STALL_SAMPLES {
+                alerts.push(Alert::new(
+                    fan.group,
+                    fan.device.as_str(),
+                    Severity::Critical,
+                    format!("{} stalled: 0 RPM while {demand}", fan.label),
+                ));
+            }
+        }
+        // Fans that spin again or went away start over.
+        self.stalled_for = stalled_for;
+        snapshot.alerts.extend(alerts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CpuTemperature, HardwareGroup};
+
+    const FAN: &str = "hwmon/nct6775/fan2";
+
+    fn fan(rpm: f64) -> Sensor {
+        Sensor::new(FAN, HardwareGroup::Board, "nct6775", "CPU Fan", SensorKind::Fan, rpm)
+    }
+
+    fn temperature(id: &str, device: &str, label: &str, celsius: f64) -> Sensor {
+        Sensor::new(id, HardwareGroup::Board, device, label, SensorKind::Temperature, celsius)
+    }
+
+    /// Alert messages for each sample in turn.
+    fn run(detector: &mut FanStallDetector, samples: Vec<Vec<Sensor>>) -> Vec<Vec<String>> {
+        samples
+            .into_iter()
+            .map(|sensors| {
+                let mut snapshot = Snapshot {
+                    sensors,
+                    ..Snapshot::default()
+                };
+                detector.collect(&mut snapshot);
+                snapshot.alerts.into_iter().map(|alert| alert.message).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stalls_while_the_temperature_rises() {
+        let mut detector = FanStallDetector::new(HashMap::new());
+        let sample = |celsius: f64| {
+            vec![fan(0.0), temperature("hwmon/nct6775/temp1", "nct6775", "SYSTIN", celsius).with_max(Some(75.0))]
+        };
+        let alerts = run(&mut detector, vec![sample(60.0), sample(70.0), sample(76.0), sample(81.0)]);
+        assert_eq!(
+            alerts,
+            [vec![], vec![], vec![], vec!["CPU Fan stalled: 0 RPM while SYSTIN is at 81.0°C".to_string()]]
+        );
+    }
+
+    #[test]
+    fn stalls_against_the_controller_target() {
+        let mut detector = FanStallDetector::new(HashMap::new());
+        let target = Sensor::new(
+            format!("{FAN}_target"),
+            HardwareGroup::Board,
+            "nct6775",
+            "CPU Fan target",
+            SensorKind::Fan,
+            1200.0,
+        );
+        let alerts = run(&mut detector, vec![vec![fan(0.0), target.clone()], vec![fan(0.0), target]]);
+        assert_eq!(alerts[0], Vec::<String>::new());
+        assert_eq!(alerts[1], ["CPU Fan stalled: 0 RPM while the controller asks for 1200 RPM"]);
+    }
+
+    #[test]
+    fn zero_rpm_idle_is_not_a_stall() {
+        let mut detector = FanStallDetector::new(HashMap::new());
+        let idle = |celsius: f64| {
+            let pwm = Sensor::new(format!("{FAN}_pwm"), HardwareGroup::Board, "nct6775", "duty", SensorKind::Load, 0.0);
+            vec![fan(0.0), pwm, temperature("hwmon/nct6775/temp1", "nct6775", "SYSTIN", celsius)]
+        };
+        // Under load the fan spins; at idle it stops and the next load warms things up.
+        let samples = vec![vec![fan(900.0)], idle(45.0), idle(47.0), idle(52.0), idle(60.0)];
+        assert!(run(&mut detector, samples).iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn uses_the_configured_temperature() {
+        let cpu = "hwmon/k10temp/0000:00:18.3/temp1";
+        let mut detector = FanStallDetector::new(HashMap::from([(FAN.into(), cpu.into())])).with_stall_temperature(70.0);
+        let sample = || {
+            vec![
+                fan(0.0),
+                temperature("hwmon/nct6775/temp1", "nct6775", "SYSTIN", 35.0),
+                temperature(cpu, "k10temp", "Tctl", 72.5),
+            ]
+        };
+        let alerts = run(&mut detector, vec![sample(), sample()]);
+        assert_eq!(alerts[1], ["CPU Fan stalled: 0 RPM while Tctl is at 72.5°C"]);
+    }
+
+    #[test]
+    fn falls_back_to_the_cpu_temperature() {
+        let cpu = "hwmon/coretemp/coretemp.0/temp1";
+        let mut detector = FanStallDetector::new(HashMap::new());
+        let mut alerts = Vec::new();
+        for _ in 0..STALL_SAMPLES {
+            let mut snapshot = Snapshot {
+                sensors: vec![fan(0.0), temperature(cpu, "coretemp", "Package id 0", 91.0)],
+                cpu_temperature: Some(CpuTemperature {
+                    sensor: cpu.into(),
+                    reason: "coretemp package",
+                }),
+                ..Snapshot::default()
+            };
+            detector.collect(&mut snapshot);
+            alerts = snapshot.alerts;
+        }
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].message, "CPU Fan stalled: 0 RPM while Package id 0 is at 91.0°C");
+        assert_eq!(alerts[0].severity, Severity::Critical);
+    }
+}
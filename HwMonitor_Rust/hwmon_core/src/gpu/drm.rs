@@ -0,0 +1,237 @@
+//! AMD and Intel GPUs through the DRM sysfs interface (`/sys/class/drm`).
+//!
+//! amdgpu exposes load, VRAM and DPM clock tables next to the PCI device and
+//! its temperatures, fan and power through a hwmon directory below it. i915
+//! only offers the current GT frequency on the card itself.
+
+use std::path::{Path, PathBuf};
+
+use crate::hwmon::{ChannelType, HwmonChannel, HwmonChip};
+use crate::sysfs::{numbered_entries, read_string, read_value};
+use crate::{HardwareGroup, Sensor, SensorKind, SensorSource, Snapshot};
+
+use super::pci_vendor_name;
+
+pub const DEFAULT_DRM_ROOT: &str = "/sys/class/drm";
+
+/// The levels of a `pp_dpm_*` table, in MHz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DpmLevels {
+    pub current: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Parses a `pp_dpm_sclk`/`pp_dpm_mclk` table such as
+///
+/// ```text
+/// 0: 500Mhz
+/// 1: 1800Mhz *
+/// ```
+///
+/// where `*` marks the active level.
+pub fn parse_dpm_levels(text: &str) -> Option<DpmLevels> {
+    let mut levels = Vec::new();
+    let mut current = None;
+
+    for line in text.lines() {
+        let Some((_, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let rest = rest.trim();
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let Ok(mhz) = rest[..digits].parse::<f64>() else {
+            continue;
+        };
+
+        levels.push(mhz);
+        if rest.ends_with('*') {
+            current = Some(mhz);
+        }
+    }
+
+    Some(DpmLevels {
+        current: current?,
+        min: levels.iter().copied().fold(f64::INFINITY, f64::min),
+        max: levels.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+/// Reads every `cardN` below the DRM root.
+pub struct DrmGpuSource {
+    root: PathBuf,
+}
+
+impl DrmGpuSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_DRM_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/class/drm`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Default for DrmGpuSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for DrmGpuSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        for card in numbered_entries(&self.root, "card") {
+            snapshot.sensors.extend(read_card(&card));
+        }
+    }
+}
+
+fn read_card(card: &Path) -> Vec<Sensor> {
+    let device = card.join("device");
+    let card_name = card.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let vendor = read_string(device.join("vendor"));
+    let device_label = match vendor.as_deref().and_then(pci_vendor_name) {
+        Some(vendor) => format!("{card_name} ({vendor})"),
+        None => card_name.clone(),
+    };
+
+    // The PCI address survives card renumbering, so ids are keyed on it.
+    let pci = std::fs::canonicalize(&device)
+        .ok()
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or(card_name);
+    let id = |name: &str| format!("drm/{pci}/{name}");
+    let sensor = |name: &str, label: &str, kind: SensorKind, value: f64| {
+        Sensor::new(id(name), HardwareGroup::Gpu, device_label.as_str(), label, kind, value)
+    };
+
+    let mut sensors = Vec::new();
+
+    if let Some(busy) = read_value::<f64>(device.join("gpu_busy_percent")) {
+        sensors.push(sensor("gpu_busy", "GPU Load", SensorKind::Load, busy));
+    }
+    if let Some(busy) = read_value::<f64>(device.join("mem_busy_percent")) {
+        sensors.push(sensor("mem_busy", "Memory Controller Load", SensorKind::Load, busy));
+    }
+
+    let vram_used = read_value::<f64>(device.join("mem_info_vram_used"));
+    let vram_total = read_value::<f64>(device.join("mem_info_vram_total"));
+    if let Some(used) = vram_used {
+        sensors.push(sensor("vram_used", "VRAM Used", SensorKind::Data, used).with_max(vram_total));
+    }
+    if let (Some(used), Some(total)) = (vram_used, vram_total.filter(|total| *total > 0.0)) {
+        sensors.push(sensor("vram_load", "VRAM Load", SensorKind::Load, used / total * 100.0));
+    }
+
+    for hwmon in numbered_entries(device.join("hwmon"), "hwmon") {
+        let Some(chip) = HwmonChip::read(&hwmon) else {
+            continue;
+        };
+        for channel in &chip.channels {
+            let label = hwmon_label(channel);
+            sensors.push(
+                sensor(&channel.attribute(), &label, channel.channel_type.kind(), channel.value)
+                    .with_min(channel.min)
+                    .with_max(channel.max)
+                    .with_critical(channel.critical),
+            );
+        }
+    }
+
+    for (file, name, label) in [("pp_dpm_sclk", "sclk", "Core Clock"), ("pp_dpm_mclk", "mclk", "Memory Clock")] {
+        let Some(levels) = read_string(device.join(file)).as_deref().and_then(parse_dpm_levels) else {
+            continue;
+        };
+        sensors.push(
+            sensor(name, label, SensorKind::Clock, levels.current)
+                .with_min(Some(levels.min))
+                .with_max(Some(levels.max)),
+        );
+    }
+
+    // i915 keeps its frequency controls on the card rather than the device.
+    if let Some(mhz) = read_value::<f64>(card.join("gt_cur_freq_mhz")) {
+        sensors.push(
+            sensor("gt_freq", "Core Clock", SensorKind::Clock, mhz)
+                .with_min(read_value(card.join("gt_min_freq_mhz")))
+                .with_max(read_value(card.join("gt_max_freq_mhz"))),
+        );
+    }
+
+    sensors
+}
+
+/// Friendlier names for the labels amdgpu gives its hwmon channels.
+fn hwmon_label(channel: &HwmonChannel) -> String {
+    match (channel.channel_type, channel.label.as_deref()) {
+        (ChannelType::Temp, Some("edge")) => "Edge Temperature".to_string(),
+        (ChannelType::Temp, Some("junction")) => "Junction Temperature".to_string(),
+        (ChannelType::Temp, Some("mem")) => "Memory Temperature".to_string(),
+        (ChannelType::Temp, None) => "GPU Temperature".to_string(),
+        (ChannelType::Fan, None) => "GPU Fan".to_string(),
+        (ChannelType::Power, None | Some("PPT")) => "GPU Power".to_string(),
+        _ => channel.display_label(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::sysfs::write_tree;
+
+    #[test]
+    fn dpm_levels_follow_the_active_marker() {
+        let levels = parse_dpm_levels("0: 500Mhz\n1: 1800Mhz *\n2: 2500Mhz\n").unwrap();
+        assert_eq!(
+            levels,
+            DpmLevels {
+                current: 1800.0,
+                min: 500.0,
+                max: 2500.0
+            }
+        );
+    }
+
+    #[test]
+    fn dpm_levels_need_an_active_level() {
+        assert_eq!(parse_dpm_levels("0: 500Mhz\n1: 1800Mhz\n"), None);
+        assert_eq!(parse_dpm_levels(""), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reads_an_amdgpu_card() {
+        let root = tempfile::tempdir().unwrap();
+        let pci = "devices/0000:03:00.0";
+        write_tree(
+            root.path(),
+            &[
+                (&format!("{pci}/vendor"), "0x1002\n"),
+                (&format!("{pci}/gpu_busy_percent"), "42\n"),
+                (&format!("{pci}/mem_info_vram_used"), "1073741824\n"),
+                (&format!("{pci}/mem_info_vram_total"), "4294967296\n"),
+                (&format!("{pci}/pp_dpm_sclk"), "0: 500Mhz\n1: 2100Mhz *\n"),
+                (&format!("{pci}/hwmon/hwmon3/name"), "amdgpu\n"),
+                (&format!("{pci}/hwmon/hwmon3/temp1_input"), "51000\n"),
+                (&format!("{pci}/hwmon/hwmon3/temp1_label"), "edge\n"),
+            ],
+        );
+        std::fs::create_dir(root.path().join("card0")).unwrap();
+        std::os::unix::fs::symlink(root.path().join(pci), root.path().join("card0/device")).unwrap();
+
+        let mut snapshot = Snapshot::default();
+        DrmGpuSource::with_root(root.path()).collect(&mut snapshot);
+        let sensor = |name: &str| snapshot.sensor(&format!("drm/0000:03:00.0/{name}").into()).unwrap();
+
+        assert_eq!(sensor("gpu_busy").value, 42.0);
+        assert_eq!(sensor("gpu_busy").device, "card0 (AMD)");
+        assert_eq!(sensor("vram_load").value, 25.0);
+        assert_eq!(sensor("vram_used").max, Some(4294967296.0));
+        assert_eq!(sensor("sclk").value, 2100.0);
+        assert_eq!((sensor("sclk").min, sensor("sclk").max), (Some(500.0), Some(2100.0)));
+        assert_eq!(sensor("temp1").value, 51.0);
+        assert_eq!(sensor("temp1").label, "Edge Temperature");
+    }
+}
@@ -0,0 +1,18 @@
+//! GPU sources. Each GPU reports its sensors under its own `device`, so
+//! consumers can show one block per card.
+
+mod drm;
+mod nvidia;
+
+pub use drm::{parse_dpm_levels, DpmLevels, DrmGpuSource, DEFAULT_DRM_ROOT};
+pub use nvidia::{parse_query_output, NvidiaGpu, NvidiaSmiSource, DEFAULT_NVIDIA_SMI, QUERY_FIELDS};
+
+/// Vendor name for a PCI vendor id as found in sysfs (`0x1002`).
+pub fn pci_vendor_name(vendor_id: &str) -> Option<&'static str> {
+    match vendor_id.trim_start_matches("0x") {
+        "1002" => Some("AMD"),
+        "8086" => Some("Intel"),
+        "10de" => Some("NVIDIA"),
+        _ => None,
+    }
+}
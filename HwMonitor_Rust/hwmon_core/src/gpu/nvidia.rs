@@ -0,0 +1,425 @@
+//! NVIDIA GPUs through `nvidia-smi --query-gpu`.
+//!
+//! The proprietary driver exposes next to nothing in sysfs, so this shells
+//! out and parses the CSV it prints. nvidia-smi can take well over a second
+//! to answer, especially with persistence mode off, so it runs on a thread of
+//! its own and every sample publishes the last answer.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot, Unit};
+
+pub const DEFAULT_NVIDIA_SMI: &str = "nvidia-smi";
+
+/// Consecutive failed runs after which nvidia-smi is no longer started; it
+/// fails the same way every time when the driver and library do not match or
+/// no GPU is present.
+const MAX_FAILURES: u32 = 5;
+
+/// How long a query may run before its GPUs' readings are dropped as stale.
+const SLOW_QUERY: Duration = Duration::from_secs(10);
+
+/// Fields requested from nvidia-smi, in the order [`parse_query_output`] expects.
+pub const QUERY_FIELDS: &str = "index,pci.bus_id,name,temperature.gpu,utilization.gpu,\
+utilization.memory,memory.used,memory.total,fan.speed,power.draw,power.limit,\
+clocks.sm,clocks.mem,clocks_throttle_reasons.active";
+
+/// One row of nvidia-smi output. Fields the GPU does not support are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NvidiaGpu {
+    pub index: u32,
+    pub bus_id: String,
+    pub name: String,
+    pub temperature: Option<f64>,
+    pub utilization: Option<f64>,
+    pub memory_utilization: Option<f64>,
+    /// MiB, as reported.
+    pub memory_used: Option<f64>,
+    pub memory_total: Option<f64>,
+    /// Percent of maximum fan speed; nvidia-smi does not report RPM.
+    pub fan_speed: Option<f64>,
+    pub power_draw: Option<f64>,
+    pub power_limit: Option<f64>,
+    pub sm_clock: Option<f64>,
+    pub memory_clock: Option<f64>,
+    /// Bitmask of `nvmlClocksThrottleReasons`.
+    pub throttle_reasons: u64,
+}
+
+/// Parses `--format=csv,noheader,nounits` output for [`QUERY_FIELDS`].
+/// Lines that do not have the expected shape are skipped. The name is the
+/// only field that can contain a comma, so the fields around it are split
+/// off from both ends of the line.
+pub fn parse_query_output(text: &str) -> Vec<NvidiaGpu> {
+    text.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<NvidiaGpu> {
+    let mut front = line.splitn(3, ',');
+    let (index, bus_id, rest) = (front.next()?, front.next()?, front.next()?);
+    let mut fields: Vec<&str> = rest.rsplitn(12, ',').map(str::trim).collect();
+    fields.reverse();
+    fields.splice(0..0, [index.trim(), bus_id.trim()]);
+    let [index, bus_id, name, temperature, utilization, memory_utilization, memory_used, memory_total, fan_speed, power_draw, power_limit, sm_clock, memory_clock, throttle] =
+        fields.as_slice()
+    else {
+        return None;
+    };
+
+    // Unsupported fields come back as "[N/A]" or "[Not Supported]".
+    let number = |field: &str| field.parse::<f64>().ok();
+
+    Some(NvidiaGpu {
+        index: index.parse().ok()?,
+        bus_id: bus_id.to_string(),
+        name: name.to_string(),
+        temperature: number(temperature),
+        utilization: number(utilization),
+        memory_utilization: number(memory_utilization),
+        memory_used: number(memory_used),
+        memory_total: number(memory_total),
+        fan_speed: number(fan_speed),
+        power_draw: number(power_draw),
+        power_limit: number(power_limit),
+        sm_clock: number(sm_clock),
+        memory_clock: number(memory_clock),
+        throttle_reasons: u64::from_str_radix(throttle.trim_start_matches("0x"), 16).unwrap_or(0),
+    })
+}
+
+/// `(bit, description, severity)` for the throttle reasons worth surfacing;
+/// idle and application-clock limits are normal operation.
+const THROTTLE_REASONS: &[(u64, &str, Severity)] = &[
+    (0x4, "power capped", Severity::Info),
+    (0x8, "hardware slowdown", Severity::Warning),
+    (0x10, "sync boost", Severity::Info),
+    (0x20, "thermal slowdown (software)", Severity::Warning),
+    (0x40, "thermal slowdown (hardware)", Severity::Critical),
+    (0x80, "power brake slowdown", Severity::Critical),
+];
+
+impl NvidiaGpu {
+    /// The `device` its sensors are reported under.
+    pub fn device(&self) -> String {
+        format!("GPU {} ({})", self.index, self.name)
+    }
+
+    pub fn sensors(&self) -> Vec<Sensor> {
+        let device = self.device();
+        let sensor = |name: &str, label: &str, kind: SensorKind, value: f64| {
+            Sensor::new(
+                format!("nvidia/{}/{name}", self.bus_id),
+                HardwareGroup::Gpu,
+                device.as_str(),
+                label,
+                kind,
+                value,
+            )
+        };
+        let mib = |value: f64| value * 1024.0 * 1024.0;
+
+        let mut sensors = Vec::new();
+        if let Some(value) = self.temperature {
+            sensors.push(sensor("temperature", "GPU Temperature", SensorKind::Temperature, value));
+        }
+        if let Some(value) = self.utilization {
+            sensors.push(sensor("utilization", "GPU Load", SensorKind::Load, value));
+        }
+        if let Some(value) = self.memory_utilization {
+            sensors.push(sensor("memory_utilization", "Memory Controller Load", SensorKind::Load, value));
+        }
+        if let Some(value) = self.memory_used {
+            sensors.push(
+                sensor("memory_used", "VRAM Used", SensorKind::Data, mib(value))
+                    .with_max(self.memory_total.map(mib)),
+            );
+        }
+        if let Some(value) = self.fan_speed {
+            sensors.push(sensor("fan", "GPU Fan", SensorKind::Fan, value).with_unit(Unit::Percent));
+        }
+        if let Some(value) = self.power_draw {
+            sensors.push(sensor("power", "GPU Power", SensorKind::Power, value).with_max(self.power_limit));
+        }
+        if let Some(value) = self.sm_clock {
+            sensors.push(sensor("sm_clock", "Core Clock", SensorKind::Clock, value));
+        }
+        if let Some(value) = self.memory_clock {
+            sensors.push(sensor("memory_clock", "Memory Clock", SensorKind::Clock, value));
+        }
+        sensors
+    }
+
+    pub fn alerts(&self) -> Vec<Alert> {
+        THROTTLE_REASONS
+            .iter()
+            .filter(|(bit, _, _)| self.throttle_reasons & bit != 0)
+            .map(|(_, reason, severity)| {
+                Alert::new(HardwareGroup::Gpu, self.device(), *severity, format!("Throttling: {reason}"))
+            })
+            .collect()
+    }
+}
+
+type QueryResult = io::Result<Vec<NvidiaGpu>>;
+
+fn run_query(command: &Path) -> QueryResult {
+    let output = Command::new(command)
+        .arg(format!("--query-gpu={QUERY_FIELDS}"))
+        .arg("--format=csv,noheader,nounits")
+        .output()?;
+    if !output.status.success() {
+        // nvidia-smi explains itself on stdout, e.g. "No devices were found".
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let reason = stdout.lines().next().map(str::trim).filter(|line| !line.is_empty());
+        return Err(io::Error::other(match reason {
+            Some(reason) => format!("nvidia-smi exited with {}: {reason}", output.status),
+            None => format!("nvidia-smi exited with {}", output.status),
+        }));
+    }
+    Ok(parse_query_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+pub struct NvidiaSmiSource {
+    command: PathBuf,
+    /// Cleared once the command turns out not to exist, so machines without
+    /// an NVIDIA driver do not spawn a failing process every sample.
+    available: bool,
+    failures: u32,
+    /// Why the last run failed; kept as an alert after giving up.
+    last_error: Option<String>,
+    /// GPUs from the last successful run.
+    gpus: Vec<NvidiaGpu>,
+    /// A run still going, with when it started.
+    pending: Option<(Instant, Receiver<QueryResult>)>,
+}
+
+impl NvidiaSmiSource {
+    pub fn new() -> Self {
+        Self::with_command(DEFAULT_NVIDIA_SMI)
+    }
+
+    /// Runs `command` instead of `nvidia-smi`, e.g. a stub printing canned output.
+    pub fn with_command(command: impl Into<PathBuf>) -> Self {
+        Self {
+            command: command.into(),
+            available: true,
+            failures: 0,
+            last_error: None,
+            gpus: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Queries nvidia-smi on the calling thread.
+    pub fn query(&self) -> io::Result<Vec<NvidiaGpu>> {
+        run_query(&self.command)
+    }
+
+    /// Picks up a finished run and starts the next one.
+    fn poll(&mut self) {
+        if let Some((_, receiver)) = &self.pending {
+            let result = match receiver.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => Err(io::Error::other("nvidia-smi query thread panicked")),
+            };
+            self.pending = None;
+            match result {
+                Ok(gpus) => {
+                    self.failures = 0;
+                    self.last_error = None;
+                    self.gpus = gpus;
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    self.available = false;
+                    self.gpus.clear();
+                }
+                Err(e) => {
+                    self.failures += 1;
+                    self.available = self.failures < MAX_FAILURES;
+                    self.last_error = Some(e.to_string());
+                    self.gpus.clear();
+                }
+            }
+        }
+
+        if self.available && self.pending.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            let command = self.command.clone();
+            thread::spawn(move || {
+                let _ = sender.send(run_query(&command));
+            });
+            self.pending = Some((Instant::now(), receiver));
+        }
+    }
+}
+
+impl Default for NvidiaSmiSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for NvidiaSmiSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        self.poll();
+
+        if let Some(started) = self.pending.as_ref().map(|(started, _)| *started) {
+            if started.elapsed() >= SLOW_QUERY {
+                self.gpus.clear();
+                snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Gpu,
+                    "nvidia-smi",
+                    Severity::Warning,
+                    format!("nvidia-smi has not answered for {} s", started.elapsed().as_secs()),
+                ));
+            }
+        }
+        for gpu in &self.gpus {
+            snapshot.sensors.extend(gpu.sensors());
+            snapshot.alerts.extend(gpu.alerts());
+        }
+
+        if let Some(error) = &self.last_error {
+            let message = if self.available {
+                error.clone()
+            } else {
+                format!("{error}; stopped querying after {MAX_FAILURES} failures")
+            };
+            snapshot.alerts.push(Alert::new(HardwareGroup::Gpu, "nvidia-smi", Severity::Warning, message));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A stand-in for nvidia-smi that prints `stdout` and exits with `status`.
+    #[cfg(unix)]
+    fn stub(dir: &std::path::Path, stdout: &str, status: i32) -> PathBuf {
+        let path = dir.join("nvidia-smi");
+        std::fs::write(&path, format!("#!/bin/sh\ncat <<'EOF'\n{stdout}EOF\nexit {status}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    const TWO_GPUS: &str = "\
+0, 00000000:01:00.0, NVIDIA GeForce RTX 3080, 64, 97, 41, 8123, 10240, 55, 301.25, 320.00, 1905, 9501, 0x0000000000000004
+1, 00000000:02:00.0, Tesla K80, 38, 0, 0, 0, 11441, [N/A], [Not Supported], [Not Supported], 324, 2505, 0x0000000000000060
+";
+
+    #[test]
+    fn parses_several_gpus() {
+        let gpus = parse_query_output(TWO_GPUS);
+        assert_eq!(gpus.len(), 2);
+
+        let rtx = &gpus[0];
+        assert_eq!(rtx.index, 0);
+        assert_eq!(rtx.bus_id, "00000000:01:00.0");
+        assert_eq!(rtx.name, "NVIDIA GeForce RTX 3080");
+        assert_eq!(rtx.temperature, Some(64.0));
+        assert_eq!(rtx.memory_total, Some(10240.0));
+        assert_eq!(rtx.power_draw, Some(301.25));
+        assert_eq!(rtx.throttle_reasons, 0x4);
+
+        let tesla = &gpus[1];
+        assert_eq!(tesla.index, 1);
+        assert_eq!(tesla.memory_clock, Some(2505.0));
+    }
+
+    #[test]
+    fn unsupported_fields_are_none() {
+        let tesla = &parse_query_output(TWO_GPUS)[1];
+        assert_eq!(tesla.fan_speed, None);
+        assert_eq!(tesla.power_draw, None);
+        assert_eq!(tesla.power_limit, None);
+
+        let names: Vec<String> = tesla.sensors().iter().map(|sensor| sensor.id.as_str().to_string()).collect();
+        assert!(!names.iter().any(|id| id.ends_with("/fan") || id.ends_with("/power")));
+    }
+
+    #[test]
+    fn throttle_bitmask_becomes_alerts() {
+        let gpus = parse_query_output(TWO_GPUS);
+        let reasons = |gpu: &NvidiaGpu| -> Vec<(Severity, String)> {
+            gpu.alerts().into_iter().map(|alert| (alert.severity, alert.message)).collect()
+        };
+
+        assert_eq!(reasons(&gpus[0]), vec![(Severity::Info, "Throttling: power capped".to_string())]);
+        assert_eq!(
+            reasons(&gpus[1]),
+            vec![
+                (Severity::Warning, "Throttling: thermal slowdown (software)".to_string()),
+                (Severity::Critical, "Throttling: thermal slowdown (hardware)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        assert!(parse_query_output("No devices were found\n").is_empty());
+        assert!(parse_query_output("0, 00000000:01:00.0, 64, 97\n").is_empty());
+    }
+
+    #[test]
+    fn names_may_contain_commas() {
+        let gpus = parse_query_output(
+            "0, 00000000:01:00.0, NVIDIA RTX A2000 12GB, Laptop GPU, 64, 97, 41, 8123, 10240, 55, 70.25, 95.00, \
+             1905, 9501, 0x0000000000000000\n",
+        );
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].name, "NVIDIA RTX A2000 12GB, Laptop GPU");
+        assert_eq!(gpus[0].temperature, Some(64.0));
+        assert_eq!(gpus[0].throttle_reasons, 0);
+    }
+
+    /// Collects until `done` holds, as the sampler would every second.
+    #[cfg(unix)]
+    fn collect_until(source: &mut NvidiaSmiSource, done: impl Fn(&NvidiaSmiSource, &Snapshot) -> bool) -> Snapshot {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let mut snapshot = Snapshot::default();
+            source.collect(&mut snapshot);
+            if done(source, &snapshot) {
+                return snapshot;
+            }
+            assert!(Instant::now() < deadline, "gave up waiting for nvidia-smi");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_the_configured_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut source = NvidiaSmiSource::with_command(stub(dir.path(), TWO_GPUS, 0));
+        let snapshot = collect_until(&mut source, |_, snapshot| !snapshot.sensors.is_empty());
+
+        let sensor = snapshot.sensor(&"nvidia/00000000:01:00.0/memory_used".into()).unwrap();
+        assert_eq!(sensor.value, 8123.0 * 1024.0 * 1024.0);
+        assert_eq!(sensor.device, "GPU 0 (NVIDIA GeForce RTX 3080)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_running_a_failing_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut source = NvidiaSmiSource::with_command(stub(dir.path(), "No devices were found\n", 6));
+        let snapshot = collect_until(&mut source, |source, _| !source.available);
+        assert_eq!(source.failures, MAX_FAILURES);
+        assert!(source.pending.is_none());
+
+        assert_eq!(snapshot.alerts.len(), 1);
+        assert!(snapshot.alerts[0].message.contains("No devices were found"));
+        assert!(snapshot.alerts[0].message.ends_with("stopped querying after 5 failures"));
+        assert!(snapshot.alerts[0].message.contains("stopped querying"));
+    }
+}
@@ -0,0 +1,469 @@
+//! Reader for the Linux hwmon sysfs interface (`/sys/class/hwmon`).
+//!
+//! See the kernel's `Documentation/hwmon/sysfs-interface.rst` for the
+//! attribute naming and units this module relies on.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sysfs::{numbered_entries, read_string, read_value};
+use crate::{Alert, HardwareGroup, Sensor, SensorId, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_HWMON_ROOT: &str = "/sys/class/hwmon";
+
+/// The hwmon channel types we understand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChannelType {
+    Temp,
+    Fan,
+    In,
+    Curr,
+    Power,
+}
+
+impl ChannelType {
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "temp" => Some(ChannelType::Temp),
+            "fan" => Some(ChannelType::Fan),
+            "in" => Some(ChannelType::In),
+            "curr" => Some(ChannelType::Curr),
+            "power" => Some(ChannelType::Power),
+            _ => None,
+        }
+    }
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ChannelType::Temp => "temp",
+            ChannelType::Fan => "fan",
+            ChannelType::In => "in",
+            ChannelType::Curr => "curr",
+            ChannelType::Power => "power",
+        }
+    }
+
+    pub fn kind(self) -> SensorKind {
+        match self {
+            ChannelType::Temp => SensorKind::Temperature,
+            ChannelType::Fan => SensorKind::Fan,
+            ChannelType::In => SensorKind::Voltage,
+            ChannelType::Curr => SensorKind::Current,
+            ChannelType::Power => SensorKind::Power,
+        }
+    }
+
+    /// Voltage, current and power: the rails of the Board/Power group.
+    pub fn is_rail(self) -> bool {
+        matches!(self, ChannelType::In | ChannelType::Curr | ChannelType::Power)
+    }
+
+    /// Divisor turning the raw sysfs integer into the sensor model's unit
+    /// (millidegrees, millivolts, milliamps and microwatts; fans are plain RPM).
+    fn scale(self) -> f64 {
+        match self {
+            ChannelType::Temp | ChannelType::In | ChannelType::Curr => 1_000.0,
+            ChannelType::Fan => 1.0,
+            ChannelType::Power => 1_000_000.0,
+        }
+    }
+}
+
+/// One `<type><N>_*` attribute family, already scaled.
+#[derive(Debug, Clone)]
+pub struct HwmonChannel {
+    pub channel_type: ChannelType,
+    pub index: u32,
+    pub label: Option<String>,
+    pub value: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub critical: Option<f64>,
+    /// Speed the fan controller is aiming for (`fanN_target`).
+    pub target: Option<f64>,
+    /// Duty cycle of the fan's PWM output (`pwmN`), in percent. Assumes the
+    /// output with the fan's index drives it, as on most boards.
+    pub pwm: Option<f64>,
+    /// The chip's own out-of-limits flag (`<stem>_alarm`).
+    pub alarm: bool,
+}
+
+impl HwmonChannel {
+    /// Attribute stem such as `temp1`.
+    pub fn attribute(&self) -> String {
+        format!("{}{}", self.channel_type.prefix(), self.index)
+    }
+
+    /// The driver-provided label, or the attribute stem when there is none.
+    pub fn display_label(&self) -> String {
+        self.label.clone().unwrap_or_else(|| self.attribute())
+    }
+}
+
+/// A single `hwmonN` directory.
+#[derive(Debug, Clone)]
+pub struct HwmonChip {
+    /// Driver name from the `name` attribute, e.g. `k10temp`.
+    pub name: String,
+    pub path: PathBuf,
+    /// Resolved `device` link, when the chip is backed by a real device.
+    pub device: Option<PathBuf>,
+    pub channels: Vec<HwmonChannel>,
+}
+
+impl HwmonChip {
+    /// Reads one `hwmonN` directory; `None` if it has no `name`.
+    pub fn read(path: &Path) -> Option<Self> {
+        let name = read_string(path.join("name"))?;
+        let device = fs::canonicalize(path.join("device")).ok();
+
+        let mut channels: Vec<HwmonChannel> = fs::read_dir(path)
+            .ok()?
+            .filter_map(Result::ok)
+            .filter_map(|entry| read_channel(path, entry.file_name().to_str()?))
+            .collect();
+        channels.sort_by_key(|channel| (channel.channel_type, channel.index));
+
+        Some(Self {
+            name,
+            path: path.to_path_buf(),
+            device,
+            channels,
+        })
+    }
+
+    /// Basename of the backing device (a PCI, I2C or platform address), which
+    /// unlike the `hwmonN` number stays the same across reboots.
+    pub fn device_name(&self) -> Option<String> {
+        let device = self.device.as_ref()?;
+        Some(device.file_name()?.to_string_lossy().into_owned())
+    }
+
+    pub fn group(&self) -> HardwareGroup {
+        group_for_driver(&self.name)
+    }
+
+    /// The group a channel's sensor goes in; rails on board-level chips
+    /// (Super I/O, PMBus) get a group of their own.
+    pub fn channel_group(&self, channel: &HwmonChannel) -> HardwareGroup {
+        match self.group() {
+            HardwareGroup::Board if channel.channel_type.is_rail() => HardwareGroup::Power,
+            group => group,
+        }
+    }
+
+    /// Prefix shared by the ids of every sensor on this chip.
+    pub fn id_prefix(&self) -> String {
+        match self.device_name() {
+            Some(device) => format!("hwmon/{}/{}", self.name, device),
+            None => format!("hwmon/{}", self.name),
+        }
+    }
+
+    pub fn sensor(&self, channel: &HwmonChannel) -> Sensor {
+        Sensor::new(
+            format!("{}/{}", self.id_prefix(), channel.attribute()),
+            self.channel_group(channel),
+            self.name.as_str(),
+            channel.display_label(),
+            channel.channel_type.kind(),
+            channel.value,
+        )
+        .with_min(channel.min)
+        .with_max(channel.max)
+        .with_critical(channel.critical)
+    }
+
+    /// The channel's `_target` as a sensor of its own, if it has one.
+    pub fn target_sensor(&self, channel: &HwmonChannel) -> Option<Sensor> {
+        let target = channel.target?;
+        Some(Sensor::new(
+            format!("{}/{}_target", self.id_prefix(), channel.attribute()),
+            self.channel_group(channel),
+            self.name.as_str(),
+            format!("{} target", channel.display_label()),
+            channel.channel_type.kind(),
+            target,
+        ))
+    }
+
+    /// The channel's PWM duty cycle as a sensor of its own, if it has one.
+    pub fn pwm_sensor(&self, channel: &HwmonChannel) -> Option<Sensor> {
+        let pwm = channel.pwm?;
+        Some(Sensor::new(
+            format!("{}/{}_pwm", self.id_prefix(), channel.attribute()),
+            self.channel_group(channel),
+            self.name.as_str(),
+            format!("{} duty", channel.display_label()),
+            SensorKind::Load,
+            pwm,
+        ))
+    }
+
+    pub fn sensors(&self) -> impl Iterator<Item = Sensor> + '_ {
+        self.channels.iter().map(|channel| self.sensor(channel))
+    }
+}
+
+/// Parses `file_name` as the input attribute of a channel and reads the rest
+/// of the family next to it.
+fn read_channel(dir: &Path, file_name: &str) -> Option<HwmonChannel> {
+    let (stem, attr) = file_name.split_once('_')?;
+    let digits = stem.find(|c: char| c.is_ascii_digit())?;
+    let channel_type = ChannelType::from_prefix(&stem[..digits])?;
+    let index = stem[digits..].parse().ok()?;
+
+    // Some drivers (amdgpu among them) only expose an averaged power reading.
+    let is_input = attr == "input"
+        || (channel_type == ChannelType::Power
+            && attr == "average"
+            && !dir.join(format!("{stem}_input")).exists());
+    if !is_input {
+        return None;
+    }
+
+    let scale = channel_type.scale();
+    let scaled = |suffix: &str| read_value::<f64>(dir.join(format!("{stem}_{suffix}"))).map(|v| v / scale);
+
+    // Super I/O chips leave limits nobody programmed at 0, which would make
+    // every rail look out of range.
+    let (mut min, mut max) = (scaled("min"), scaled("max"));
+    if let (Some(low), Some(high)) = (min, max) {
+        if channel_type.is_rail() && high <= low {
+            (min, max) = (None, None);
+        }
+    }
+
+    Some(HwmonChannel {
+        channel_type,
+        index,
+        label: read_string(dir.join(format!("{stem}_label"))).filter(|label| !label.is_empty()),
+        value: scaled(attr)?,
+        min,
+        max,
+        critical: scaled("crit"),
+        target: scaled("target"),
+        // `pwmN` runs from 0 to 255.
+        pwm: (channel_type == ChannelType::Fan)
+            .then(|| read_value::<f64>(dir.join(format!("pwm{index}"))))
+            .flatten()
+            .map(|pwm| pwm / 255.0 * 100.0),
+        alarm: read_value::<u8>(dir.join(format!("{stem}_alarm"))) == Some(1),
+    })
+}
+
+/// Which part of the machine a driver's readings describe.
+pub fn group_for_driver(name: &str) -> HardwareGroup {
+    match name {
+        "k10temp" | "coretemp" | "zenpower" | "cpu_thermal" | "via_cputemp" => HardwareGroup::Cpu,
+        "amdgpu" | "radeon" | "nouveau" | "i915" | "xe" => HardwareGroup::Gpu,
+        "jc42" | "spd5118" => HardwareGroup::Memory,
+        "nvme" | "drivetemp" => HardwareGroup::Storage,
+        _ => HardwareGroup::Board,
+    }
+}
+
+/// Every chip below `root`, in `hwmonN` order.
+pub fn read_chips(root: &Path) -> Vec<HwmonChip> {
+    numbered_entries(root, "hwmon")
+        .iter()
+        .filter_map(|path| HwmonChip::read(path))
+        .collect()
+}
+
+/// Publishes every hwmon channel as a sensor.
+pub struct HwmonSource {
+    root: PathBuf,
+    skipped: Vec<HardwareGroup>,
+    labels: HashMap<SensorId, String>,
+    multipliers: HashMap<String, f64>,
+}
+
+impl HwmonSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_HWMON_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/class/hwmon`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            skipped: Vec::new(),
+            labels: HashMap::new(),
+            multipliers: HashMap::new(),
+        }
+    }
+
+    /// Leaves chips in `group` to a source that reports them in more detail.
+    pub fn skip_group(mut self, group: HardwareGroup) -> Self {
+        self.skipped.push(group);
+        self
+    }
+
+    /// Replaces driver labels by sensor id, e.g. to name fan headers.
+    pub fn with_labels(mut self, labels: HashMap<SensorId, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Scales rails by a factor keyed by sensor id or by chip id prefix
+    /// (`hwmon/nct6775/nct6775.656`), for inputs behind a voltage divider.
+    pub fn with_multipliers(mut self, multipliers: HashMap<String, f64>) -> Self {
+        self.multipliers = multipliers;
+        self
+    }
+
+    fn multiplier(&self, chip: &HwmonChip, sensor: &Sensor) -> Option<f64> {
+        self.multipliers
+            .get(sensor.id.as_str())
+            .or_else(|| self.multipliers.get(&chip.id_prefix()))
+            .copied()
+    }
+
+    pub fn chips(&self) -> Vec<HwmonChip> {
+        read_chips(&self.root)
+    }
+}
+
+impl Default for HwmonSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for HwmonSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        for chip in self.chips().into_iter().filter(|chip| !self.skipped.contains(&chip.group())) {
+            for channel in &chip.channels {
+                let mut sensor = chip.sensor(channel);
+                let mut target = chip.target_sensor(channel);
+                let mut pwm = chip.pwm_sensor(channel);
+                if let Some(factor) = self.multiplier(&chip, &sensor).filter(|_| channel.channel_type.is_rail()) {
+                    sensor.value *= factor;
+                    sensor.min = sensor.min.map(|min| min * factor);
+                    sensor.max = sensor.max.map(|max| max * factor);
+                    sensor.critical = sensor.critical.map(|critical| critical * factor);
+                }
+                if let Some(label) = self.labels.get(&sensor.id) {
+                    sensor.label = label.clone();
+                    if let Some(target) = &mut target {
+                        target.label = format!("{label} target");
+                    }
+                    if let Some(pwm) = &mut pwm {
+                        pwm.label = format!("{label} duty");
+                    }
+                }
+                if channel.alarm {
+                    snapshot.alerts.push(Alert::new(
+                        sensor.group,
+                        chip.name.as_str(),
+                        Severity::Warning,
+                        format!("{} alarm", sensor.label),
+                    ));
+                }
+                snapshot.sensors.push(sensor);
+                snapshot.sensors.extend(target);
+                snapshot.sensors.extend(pwm);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    fn collect(root: &Path) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        HwmonSource::with_root(root).collect(&mut snapshot);
+        snapshot
+    }
+
+    #[test]
+    fn scales_channels_and_reads_limits() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("hwmon0/name", "nct6775\n"),
+                ("hwmon0/temp1_input", "45500\n"),
+                ("hwmon0/temp1_label", "SYSTIN\n"),
+                ("hwmon0/temp1_crit", "95000\n"),
+                ("hwmon0/fan2_input", "1200\n"),
+                ("hwmon0/in1_input", "3312\n"),
+                ("hwmon0/in1_min", "3000\n"),
+                ("hwmon0/in1_max", "3600\n"),
+                ("hwmon0/curr1_input", "1500\n"),
+                ("hwmon0/power1_average", "25000000\n"),
+                ("hwmon0/power1_label", "PPT\n"),
+            ],
+        );
+        let snapshot = collect(root.path());
+        let sensor = |id: &str| snapshot.sensor(&id.into()).unwrap_or_else(|| panic!("no sensor {id}"));
+
+        let temp = sensor("hwmon/nct6775/temp1");
+        assert_eq!(temp.value, 45.5);
+        assert_eq!(temp.label, "SYSTIN");
+        assert_eq!(temp.critical, Some(95.0));
+        assert_eq!(temp.group, HardwareGroup::Board);
+
+        let fan = sensor("hwmon/nct6775/fan2");
+        assert_eq!(fan.value, 1200.0);
+        assert_eq!(fan.label, "fan2");
+
+        let rail = sensor("hwmon/nct6775/in1");
+        assert_eq!(rail.value, 3.312);
+        assert_eq!((rail.min, rail.max), (Some(3.0), Some(3.6)));
+        assert_eq!(rail.group, HardwareGroup::Power);
+
+        assert_eq!(sensor("hwmon/nct6775/curr1").value, 1.5);
+
+        let power = sensor("hwmon/nct6775/power1");
+        assert_eq!(power.value, 25.0);
+        assert_eq!(power.label, "PPT");
+    }
+
+    #[test]
+    fn prefers_power_input_over_average() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("hwmon0/name", "amdgpu\n"),
+                ("hwmon0/power1_input", "30000000\n"),
+                ("hwmon0/power1_average", "25000000\n"),
+            ],
+        );
+        let snapshot = collect(root.path());
+        assert_eq!(snapshot.sensors.len(), 1);
+        assert_eq!(snapshot.sensors[0].value, 30.0);
+    }
+
+    #[test]
+    fn clears_unprogrammed_rail_limits() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("hwmon0/name", "nct6775\n"),
+                ("hwmon0/in0_input", "1104\n"),
+                ("hwmon0/in0_min", "0\n"),
+                ("hwmon0/in0_max", "0\n"),
+                ("hwmon0/temp1_input", "30000\n"),
+                ("hwmon0/temp1_min", "0\n"),
+                ("hwmon0/temp1_max", "0\n"),
+            ],
+        );
+        let snapshot = collect(root.path());
+
+        let rail = snapshot.sensor(&"hwmon/nct6775/in0".into()).unwrap();
+        assert_eq!((rail.min, rail.max), (None, None));
+        assert!(!rail.out_of_range());
+
+        // Only rails are cleared; temperature limits are left as the driver reports them.
+        let temp = snapshot.sensor(&"hwmon/nct6775/temp1".into()).unwrap();
+        assert_eq!((temp.min, temp.max), (Some(0.0), Some(0.0)));
+    }
+}
@@ -0,0 +1,635 @@
+//! A static hardware inventory for support tickets: board and BIOS from DMI
+//! (`/sys/class/dmi/id`), CPU from `/proc/cpuinfo`, memory size and DIMMs
+//! (from EDAC where a driver is loaded, else from the modules' jc42/spd5118
+//! thermal sensors), GPUs from the DRM class, drives from `/sys/block` and
+//! the kernel release.
+//!
+//! Everything is read once and can be exported as JSON or Markdown. Lists
+//! are kept in a stable order so two inventories of the same machine are
+//! identical.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::edac::DEFAULT_EDAC_ROOT;
+use crate::gpu::{pci_vendor_name, DEFAULT_DRM_ROOT};
+use crate::hwmon::DEFAULT_HWMON_ROOT;
+use crate::memory::{dimm_chips, parse_meminfo, DEFAULT_MEMINFO};
+use crate::storage::{StorageSource, DEFAULT_BLOCK_ROOT};
+use crate::sysfs::{numbered_entries, read_string, read_value};
+use crate::format_bytes;
+
+pub const DEFAULT_DMI_ROOT: &str = "/sys/class/dmi/id";
+pub const DEFAULT_CPUINFO: &str = "/proc/cpuinfo";
+pub const DEFAULT_OSRELEASE: &str = "/proc/sys/kernel/osrelease";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Inventory {
+    pub system: SystemInfo,
+    pub cpu: CpuInfo,
+    /// Installed memory in bytes, as the kernel sees it.
+    pub memory: u64,
+    pub dimms: Vec<DimmInfo>,
+    pub gpus: Vec<GpuInfo>,
+    pub disks: Vec<DiskInventory>,
+    pub kernel: Option<String>,
+}
+
+/// Board, product and firmware from DMI.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SystemInfo {
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    pub board_vendor: Option<String>,
+    pub board: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    pub bios_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CpuInfo {
+    pub model: Option<String>,
+    pub vendor: Option<String>,
+    pub microcode: Option<String>,
+    pub packages: usize,
+    pub cores: usize,
+    pub threads: usize,
+    /// Feature flags, sorted.
+    pub flags: Vec<String>,
+}
+
+/// A memory module as the EDAC driver or the module's thermal sensor reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DimmInfo {
+    /// Firmware label such as `CPU_SrcID#0_Ha#0_Chan#1_DIMM#0`, else `mc0 dimm3`,
+    /// or the sensor's slot such as `DIMM 2 (bus 0, 0x1a)`.
+    pub label: String,
+    /// Bytes; unknown when only a thermal sensor shows the module is there.
+    pub size: Option<u64>,
+    /// Memory type such as `Registered-DDR4`.
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuInfo {
+    /// PCI address such as `0000:03:00.0`.
+    pub address: String,
+    pub vendor: String,
+    /// PCI device id, e.g. `0x73bf`.
+    pub device_id: Option<String>,
+    pub driver: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskInventory {
+    pub name: String,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub firmware: Option<String>,
+    pub kind: String,
+    pub size: u64,
+}
+
+/// Parses `/proc/cpuinfo`. x86 lists one block per logical CPU with
+/// `physical id` and `core id`; ARM has neither, so every CPU counts as a core.
+pub fn parse_cpuinfo(text: &str) -> CpuInfo {
+    let mut info = CpuInfo::default();
+    let mut flags = BTreeSet::new();
+    let mut packages = BTreeSet::new();
+    let mut cores = BTreeSet::new();
+    let mut package = None;
+
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "processor" => {
+                info.threads += 1;
+                package = None;
+            }
+            // ARM has no `model name`; `Hardware` names the SoC, `Model` the board.
+            "model name" | "Hardware" | "Model" if info.model.is_none() => info.model = Some(value.to_string()),
+            "vendor_id" | "CPU implementer" if info.vendor.is_none() => info.vendor = Some(value.to_string()),
+            "microcode" if info.microcode.is_none() => info.microcode = Some(value.to_string()),
+            "flags" | "Features" => flags.extend(value.split_whitespace().map(str::to_string)),
+            "physical id" => {
+                packages.insert(value.to_string());
+                package = Some(value.to_string());
+            }
+            "core id" => {
+                cores.insert((package.clone(), value.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    info.packages = packages.len().max(1);
+    info.cores = if cores.is_empty() { info.threads } else { cores.len() };
+    info.flags = flags.into_iter().collect();
+    info
+}
+
+/// SCSI VPD page 0x80 (unit serial number): a four-byte header, then ASCII.
+fn parse_vpd_serial(page: &[u8]) -> Option<String> {
+    let serial = String::from_utf8_lossy(page.get(4..)?).trim().trim_matches('\0').to_string();
+    (!serial.is_empty()).then_some(serial)
+}
+
+/// Reads an [`Inventory`]; each `with_*` builder overrides one location.
+pub struct InventoryReader {
+    dmi_root: PathBuf,
+    cpuinfo: PathBuf,
+    meminfo: PathBuf,
+    edac_root: PathBuf,
+    hwmon_root: PathBuf,
+    drm_root: PathBuf,
+    block_root: PathBuf,
+    osrelease: PathBuf,
+}
+
+impl InventoryReader {
+    pub fn new() -> Self {
+        Self {
+            dmi_root: DEFAULT_DMI_ROOT.into(),
+            cpuinfo: DEFAULT_CPUINFO.into(),
+            meminfo: DEFAULT_MEMINFO.into(),
+            edac_root: DEFAULT_EDAC_ROOT.into(),
+            hwmon_root: DEFAULT_HWMON_ROOT.into(),
+            drm_root: DEFAULT_DRM_ROOT.into(),
+            block_root: DEFAULT_BLOCK_ROOT.into(),
+            osrelease: DEFAULT_OSRELEASE.into(),
+        }
+    }
+
+    pub fn with_dmi_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.dmi_root = root.into();
+        self
+    }
+
+    pub fn with_cpuinfo(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cpuinfo = path.into();
+        self
+    }
+
+    pub fn with_meminfo(mut self, path: impl Into<PathBuf>) -> Self {
+        self.meminfo = path.into();
+        self
+    }
+
+    pub fn with_edac_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.edac_root = root.into();
+        self
+    }
+
+    pub fn with_hwmon_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.hwmon_root = root.into();
+        self
+    }
+
+    pub fn with_drm_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.drm_root = root.into();
+        self
+    }
+
+    pub fn with_block_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.block_root = root.into();
+        self
+    }
+
+    pub fn with_osrelease(mut self, path: impl Into<PathBuf>) -> Self {
+        self.osrelease = path.into();
+        self
+    }
+
+    pub fn read(&self) -> Inventory {
+        Inventory {
+            system: self.system(),
+            cpu: read_string(&self.cpuinfo).map(|text| parse_cpuinfo(&text)).unwrap_or_default(),
+            memory: read_string(&self.meminfo).map_or(0, |text| parse_meminfo(&text).total),
+            dimms: self.dimms(),
+            gpus: self.gpus(),
+            disks: self.disks(),
+            kernel: read_string(&self.osrelease),
+        }
+    }
+
+    fn system(&self) -> SystemInfo {
+        let dmi = |name: &str| read_string(self.dmi_root.join(name)).filter(|value| !is_placeholder(value));
+        SystemInfo {
+            vendor: dmi("sys_vendor"),
+            product: dmi("product_name"),
+            board_vendor: dmi("board_vendor"),
+            board: dmi("board_name"),
+            bios_vendor: dmi("bios_vendor"),
+            bios_version: dmi("bios_version"),
+            bios_date: dmi("bios_date"),
+        }
+    }
+
+    /// Populated DIMMs; EDAC lists empty slots with a size of zero. Without
+    /// EDAC, which needs ECC memory, modules with a thermal sensor are listed
+    /// by slot instead.
+    fn dimms(&self) -> Vec<DimmInfo> {
+        let mut dimms = Vec::new();
+        for controller in numbered_entries(&self.edac_root, "mc") {
+            let mc = controller.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            for dimm in numbered_entries(&controller, "dimm") {
+                // `size` is in MiB.
+                let size = read_value::<u64>(dimm.join("size")).unwrap_or(0) * 1024 * 1024;
+                if size == 0 {
+                    continue;
+                }
+                let name = dimm.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                dimms.push(DimmInfo {
+                    label: read_string(dimm.join("dimm_label"))
+                        .filter(|label| !label.is_empty())
+                        .unwrap_or_else(|| format!("{mc} {name}")),
+                    size: Some(size),
+                    kind: read_string(dimm.join("dimm_mem_type")),
+                });
+            }
+        }
+        if dimms.is_empty() {
+            dimms = dimm_chips(&self.hwmon_root)
+                .into_iter()
+                .map(|(label, chip)| DimmInfo {
+                    label,
+                    size: None,
+                    kind: (chip.name == "spd5118").then(|| "DDR5".to_string()),
+                })
+                .collect();
+            dimms.sort_by(|a, b| a.label.cmp(&b.label));
+        }
+        dimms
+    }
+
+    /// One entry per PCI device, even when it drives several cards.
+    fn gpus(&self) -> Vec<GpuInfo> {
+        let mut gpus: Vec<GpuInfo> = Vec::new();
+        for card in numbered_entries(&self.drm_root, "card") {
+            let device = card.join("device");
+            let Some(address) = std::fs::canonicalize(&device)
+                .ok()
+                .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            else {
+                continue;
+            };
+            if gpus.iter().any(|gpu| gpu.address == address) {
+                continue;
+            }
+            let vendor_id = read_string(device.join("vendor"));
+            gpus.push(GpuInfo {
+                vendor: vendor_id
+                    .as_deref()
+                    .map(|id| pci_vendor_name(id).map_or_else(|| id.to_string(), str::to_string))
+                    .unwrap_or_else(|| "unknown".to_string()),
+                device_id: read_string(device.join("device")),
+                driver: link_name(&device.join("driver")),
+                address,
+            });
+        }
+        gpus.sort_by(|a, b| a.address.cmp(&b.address));
+        gpus
+    }
+
+    fn disks(&self) -> Vec<DiskInventory> {
+        StorageSource::new()
+            .with_block_root(&self.block_root)
+            .drives()
+            .into_iter()
+            .map(|drive| {
+                let device = self.block_root.join(&drive.name).join("device");
+                let serial = read_string(device.join("serial"))
+                    .filter(|serial| !serial.is_empty())
+                    .or_else(|| parse_vpd_serial(&std::fs::read(device.join("vpd_pg80")).ok()?));
+                DiskInventory {
+                    model: drive.model,
+                    serial,
+                    // NVMe calls it `firmware_rev`, SCSI/SATA `rev`.
+                    firmware: read_string(device.join("firmware_rev")).or_else(|| read_string(device.join("rev"))),
+                    kind: drive.kind.name().to_string(),
+                    size: drive.size,
+                    name: drive.name,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for InventoryReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Board vendors leave unused DMI fields filled with boilerplate.
+fn is_placeholder(value: &str) -> bool {
+    matches!(value, "" | "To Be Filled By O.E.M." | "Default string" | "System Product Name" | "Not Specified")
+}
+
+/// Makes `text` safe for a Markdown table cell: a `|` would end the cell and
+/// a line break the row.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace("\r\n", " ").replace(['\n', '\r'], " ")
+}
+
+fn link_name(path: &Path) -> Option<String> {
+    Some(std::fs::read_link(path).ok()?.file_name()?.to_string_lossy().into_owned())
+}
+
+impl Inventory {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("inventory always serializes")
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let value = |value: &Option<String>| value.as_deref().map_or_else(|| "–".to_string(), cell);
+        let mut out = String::from("# Hardware inventory\n\n## System\n\n| | |\n|---|---|\n");
+        let system = &self.system;
+        for (name, field) in [
+            ("Vendor", &system.vendor),
+            ("Product", &system.product),
+            ("Board vendor", &system.board_vendor),
+            ("Board", &system.board),
+            ("BIOS vendor", &system.bios_vendor),
+            ("BIOS version", &system.bios_version),
+            ("BIOS date", &system.bios_date),
+            ("Kernel", &self.kernel),
+        ] {
+            let _ = writeln!(out, "| {name} | {} |", value(field));
+        }
+
+        let cpu = &self.cpu;
+        let _ = write!(
+            out,
+            "\n## CPU\n\n| | |\n|---|---|\n| Model | {} |\n| Vendor | {} |\n| Microcode | {} |\n\
+             | Topology | {} packages, {} cores, {} threads |\n| Flags | {} |\n",
+            value(&cpu.model),
+            value(&cpu.vendor),
+            value(&cpu.microcode),
+            cpu.packages,
+            cpu.cores,
+            cpu.threads,
+            cell(&cpu.flags.join(" ")),
+        );
+
+        let _ = write!(out, "\n## Memory\n\n{}\n", format_bytes(self.memory as f64));
+        if !self.dimms.is_empty() {
+            out.push_str("\n| DIMM | Size | Type |\n|---|---|---|\n");
+            for dimm in &self.dimms {
+                let size = dimm.size.map(|size| format_bytes(size as f64));
+                let _ = writeln!(out, "| {} | {} | {} |", cell(&dimm.label), value(&size), value(&dimm.kind));
+            }
+        }
+
+        out.push_str("\n## GPUs\n\n| Address | Vendor | Device | Driver |\n|---|---|---|---|\n");
+        for gpu in &self.gpus {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                cell(&gpu.address),
+                cell(&gpu.vendor),
+                value(&gpu.device_id),
+                value(&gpu.driver)
+            );
+        }
+
+        out.push_str("\n## Disks\n\n| Name | Model | Serial | Firmware | Type | Size |\n|---|---|---|---|---|---|\n");
+        for disk in &self.disks {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} |",
+                cell(&disk.name),
+                value(&disk.model),
+                value(&disk.serial),
+                value(&disk.firmware),
+                cell(&disk.kind),
+                format_bytes(disk.size as f64)
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    const X86_CPUINFO: &str = "\
+processor\t: 0
+vendor_id\t: AuthenticAMD
+model name\t: AMD Ryzen 7 5800X 8-Core Processor
+microcode\t: 0xa201016
+physical id\t: 0
+core id\t\t: 0
+flags\t\t: fpu sse4_2 avx2
+
+processor\t: 1
+vendor_id\t: AuthenticAMD
+model name\t: AMD Ryzen 7 5800X 8-Core Processor
+microcode\t: 0xa201016
+physical id\t: 0
+core id\t\t: 0
+flags\t\t: fpu sse4_2 avx2
+
+processor\t: 2
+vendor_id\t: AuthenticAMD
+model name\t: AMD Ryzen 7 5800X 8-Core Processor
+microcode\t: 0xa201016
+physical id\t: 0
+core id\t\t: 1
+flags\t\t: fpu sse4_2 avx2
+";
+
+    const ARM_CPUINFO: &str = "\
+processor\t: 0
+BogoMIPS\t: 108.00
+Features\t: fp asimd evtstrm crc32 cpuid
+CPU implementer\t: 0x41
+CPU part\t: 0xd08
+
+processor\t: 1
+BogoMIPS\t: 108.00
+Features\t: fp asimd evtstrm crc32 cpuid
+CPU implementer\t: 0x41
+CPU part\t: 0xd08
+
+Hardware\t: BCM2835
+Revision\t: c03114
+Model\t\t: Raspberry Pi 4 Model B Rev 1.4
+";
+
+    fn sample() -> Inventory {
+        Inventory {
+            system: SystemInfo {
+                vendor: Some("Micro-Star International Co., Ltd.".to_string()),
+                product: Some("MS-7C56".to_string()),
+                board: Some("B550-A PRO (MS-7C56)".to_string()),
+                bios_version: Some("A.C0".to_string()),
+                ..SystemInfo::default()
+            },
+            cpu: parse_cpuinfo(X86_CPUINFO),
+            memory: 32 << 30,
+            dimms: vec![DimmInfo {
+                label: "DIMM 0 (bus 0, 0x18)".to_string(),
+                size: None,
+                kind: None,
+            }],
+            gpus: vec![GpuInfo {
+                address: "0000:03:00.0".to_string(),
+                vendor: "AMD".to_string(),
+                device_id: Some("0x73bf".to_string()),
+                driver: Some("amdgpu".to_string()),
+            }],
+            disks: vec![DiskInventory {
+                name: "nvme0n1".to_string(),
+                model: Some("Samsung SSD 980 PRO 1TB".to_string()),
+                serial: Some("S5GXNF0R123456".to_string()),
+                firmware: Some("5B2QGXA7".to_string()),
+                kind: "NVMe".to_string(),
+                size: 1 << 40,
+            }],
+            kernel: Some("6.8.0-45-generic".to_string()),
+        }
+    }
+
+    #[test]
+    fn parses_x86_cpuinfo() {
+        assert_eq!(
+            parse_cpuinfo(X86_CPUINFO),
+            CpuInfo {
+                model: Some("AMD Ryzen 7 5800X 8-Core Processor".to_string()),
+                vendor: Some("AuthenticAMD".to_string()),
+                microcode: Some("0xa201016".to_string()),
+                packages: 1,
+                cores: 2,
+                threads: 3,
+                flags: vec!["avx2".to_string(), "fpu".to_string(), "sse4_2".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_arm_cpuinfo() {
+        let cpu = parse_cpuinfo(ARM_CPUINFO);
+        assert_eq!(cpu.model.as_deref(), Some("BCM2835"));
+        assert_eq!(cpu.vendor.as_deref(), Some("0x41"));
+        assert_eq!(cpu.microcode, None);
+        assert_eq!((cpu.packages, cpu.cores, cpu.threads), (1, 2, 2));
+        assert_eq!(cpu.flags, ["asimd", "cpuid", "crc32", "evtstrm", "fp"]);
+    }
+
+    #[test]
+    fn vpd_serial_skips_the_header() {
+        assert_eq!(parse_vpd_serial(b"\0\x80\0\x0c  WD-WX12A3456789\0").as_deref(), Some("WD-WX12A3456789"));
+        assert_eq!(parse_vpd_serial(b"\0\x80\0\0"), None);
+        assert_eq!(parse_vpd_serial(b"\0\x80"), None);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let inventory = sample();
+        let parsed: Inventory = serde_json::from_str(&inventory.to_json()).unwrap();
+        assert_eq!(parsed, inventory);
+    }
+
+    #[test]
+    fn markdown_report() {
+        let expected = "\
+# Hardware inventory
+
+## System
+
+| | |
+|---|---|
+| Vendor | Micro-Star International Co., Ltd. |
+| Product | MS-7C56 |
+| Board vendor | – |
+| Board | B550-A PRO (MS-7C56) |
+| BIOS vendor | – |
+| BIOS version | A.C0 |
+| BIOS date | – |
+| Kernel | 6.8.0-45-generic |
+
+## CPU
+
+| | |
+|---|---|
+| Model | AMD Ryzen 7 5800X 8-Core Processor |
+| Vendor | AuthenticAMD |
+| Microcode | 0xa201016 |
+| Topology | 1 packages, 2 cores, 3 threads |
+| Flags | avx2 fpu sse4_2 |
+
+## Memory
+
+32.0 GiB
+
+| DIMM | Size | Type |
+|---|---|---|
+| DIMM 0 (bus 0, 0x18) | – | – |
+
+## GPUs
+
+| Address | Vendor | Device | Driver |
+|---|---|---|---|
+| 0000:03:00.0 | AMD | 0x73bf | amdgpu |
+
+## Disks
+
+| Name | Model | Serial | Firmware | Type | Size |
+|---|---|---|---|---|---|
+| nvme0n1 | Samsung SSD 980 PRO 1TB | S5GXNF0R123456 | 5B2QGXA7 | NVMe | 1.0 TiB |
+";
+        assert_eq!(sample().to_markdown(), expected);
+    }
+
+    #[test]
+    fn markdown_cells_are_escaped() {
+        let mut inventory = sample();
+        inventory.system.vendor = Some("Vendor | Inc.\nSecond line".to_string());
+        inventory.disks[0].model = Some("Model\r\nX|Y".to_string());
+        let markdown = inventory.to_markdown();
+        assert!(markdown.contains("| Vendor | Vendor \\| Inc. Second line |\n"), "{markdown}");
+        assert!(markdown.contains("| nvme0n1 | Model X\\|Y | S5GXNF0R123456 |"), "{markdown}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lists_dimms_by_thermal_sensor_without_edac() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("devices/0-0051/name", ""),
+                ("hwmon/hwmon4/name", "spd5118\n"),
+                ("hwmon/hwmon4/temp1_input", "41000\n"),
+                ("hwmon/hwmon2/name", "nct6775\n"),
+            ],
+        );
+        std::os::unix::fs::symlink(root.path().join("devices/0-0051"), root.path().join("hwmon/hwmon4/device")).unwrap();
+
+        let inventory = InventoryReader::new()
+            .with_edac_root(root.path().join("edac"))
+            .with_hwmon_root(root.path().join("hwmon"))
+            .read();
+        assert_eq!(
+            inventory.dimms,
+            [DimmInfo {
+                label: "DIMM 1 (bus 0, 0x51)".to_string(),
+                size: None,
+                kind: Some("DDR5".to_string()),
+            }]
+        );
+    }
+}
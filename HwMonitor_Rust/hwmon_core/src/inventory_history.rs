@@ -0,0 +1,471 @@
+//! Hardware changes between runs. The inventory is saved on every start and
+//! compared with the one saved the time before, so a disk or DIMM that
+//! silently dropped out is reported even on machines nobody looks at.
+//!
+//! [`diff`] only looks at its two arguments; the changes between any two
+//! saved inventory files can be reproduced with [`Inventory::load`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::inventory::{DimmInfo, DiskInventory, GpuInfo};
+use crate::{format_bytes, Alert, HardwareGroup, Inventory, SensorSource, Severity, Snapshot};
+
+/// Overrides where the inventory is saved.
+pub const INVENTORY_ENV: &str = "HWMON_INVENTORY";
+
+/// Memory totals closer than this fraction count as unchanged; the amount
+/// the kernel reserves for itself moves a little between releases.
+const MEMORY_TOLERANCE: f64 = 0.02;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryChange {
+    pub group: HardwareGroup,
+    /// Warning for hardware that went missing or shrank, Info otherwise.
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl InventoryChange {
+    fn new(group: HardwareGroup, severity: Severity, message: String) -> Self {
+        Self {
+            group,
+            severity,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for InventoryChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+fn or_unknown(value: Option<&str>) -> &str {
+    value.unwrap_or("unknown")
+}
+
+fn field(changes: &mut Vec<InventoryChange>, group: HardwareGroup, what: &str, before: &Option<String>, after: &Option<String>) {
+    if before != after {
+        changes.push(InventoryChange::new(
+            group,
+            Severity::Info,
+            format!("{what} changed: {} → {}", or_unknown(before.as_deref()), or_unknown(after.as_deref())),
+        ));
+    }
+}
+
+/// Pairs up items by key: `(before, after)` for every key, in key order.
+fn paired<'a, T>(
+    previous: &'a [T],
+    current: &'a [T],
+    key: impl Fn(&T) -> String,
+) -> BTreeMap<String, (Option<&'a T>, Option<&'a T>)> {
+    let mut pairs: BTreeMap<String, (Option<&T>, Option<&T>)> = BTreeMap::new();
+    for item in previous {
+        pairs.entry(key(item)).or_default().0 = Some(item);
+    }
+    for item in current {
+        pairs.entry(key(item)).or_default().1 = Some(item);
+    }
+    pairs
+}
+
+/// e.g. `DIMM_A1 (16.0 GiB)`, or just the label when the size is unknown.
+fn describe_dimm(dimm: &DimmInfo) -> String {
+    match dimm.size {
+        Some(size) => format!("{} ({})", dimm.label, format_bytes(size as f64)),
+        None => dimm.label.clone(),
+    }
+}
+
+fn describe_gpu(gpu: &GpuInfo) -> String {
+    format!("{} {} {}", gpu.address, gpu.vendor, gpu.device_id.as_deref().unwrap_or("")).trim_end().to_string()
+}
+
+/// e.g. `nvme0n1 (Samsung SSD 980 PRO 1TB, serial S5GXNF0R123456)`.
+fn describe_disk(disk: &DiskInventory) -> String {
+    let mut details = vec![disk.model.clone().unwrap_or_else(|| format_bytes(disk.size as f64))];
+    details.extend(disk.serial.as_ref().map(|serial| format!("serial {serial}")));
+    format!("{} ({})", disk.name, details.join(", "))
+}
+
+/// What hardware changed from `previous` to `current`, always in the same
+/// order: system, CPU, memory, DIMMs, GPUs and disks, each list sorted by key.
+pub fn diff(previous: &Inventory, current: &Inventory) -> Vec<InventoryChange> {
+    let mut changes = Vec::new();
+
+    let (before, after) = (&previous.system, &current.system);
+    field(&mut changes, HardwareGroup::Board, "System vendor", &before.vendor, &after.vendor);
+    field(&mut changes, HardwareGroup::Board, "System model", &before.product, &after.product);
+    field(&mut changes, HardwareGroup::Board, "Board vendor", &before.board_vendor, &after.board_vendor);
+    field(&mut changes, HardwareGroup::Board, "Board model", &before.board, &after.board);
+    field(&mut changes, HardwareGroup::Board, "BIOS vendor", &before.bios_vendor, &after.bios_vendor);
+    field(&mut changes, HardwareGroup::Board, "BIOS version", &before.bios_version, &after.bios_version);
+    field(&mut changes, HardwareGroup::Board, "BIOS date", &before.bios_date, &after.bios_date);
+    // The kernel is left out: an upgrade is routine, not a hardware change.
+
+    // Microcode and flags are left out too: OS microcode updates and new
+    // kernels change them without any hardware changing.
+    let (before, after) = (&previous.cpu, &current.cpu);
+    field(&mut changes, HardwareGroup::Cpu, "CPU model", &before.model, &after.model);
+    if before.threads != after.threads {
+        // Fewer threads than last time usually means CPUs failed to come online.
+        let severity = if after.threads < before.threads { Severity::Warning } else { Severity::Info };
+        changes.push(InventoryChange::new(
+            HardwareGroup::Cpu,
+            severity,
+            format!("CPU threads changed: {} → {}", before.threads, after.threads),
+        ));
+    }
+
+    let (before, after) = (previous.memory as f64, current.memory as f64);
+    if (after - before).abs() > before * MEMORY_TOLERANCE {
+        let (severity, verb) = if after < before { (Severity::Warning, "shrank") } else { (Severity::Info, "grew") };
+        changes.push(InventoryChange::new(
+            HardwareGroup::Memory,
+            severity,
+            format!("Memory {verb}: {} → {}", format_bytes(before), format_bytes(after)),
+        ));
+    }
+
+    for (label, pair) in paired(&previous.dimms, &current.dimms, |dimm| dimm.label.clone()) {
+        let (severity, message) = match pair {
+            (Some(dimm), None) => (Severity::Warning, format!("DIMM missing: {}", describe_dimm(dimm))),
+            (None, Some(dimm)) => (Severity::Info, format!("New DIMM added: {}", describe_dimm(dimm))),
+            (Some(DimmInfo { size: Some(before), .. }), Some(DimmInfo { size: Some(after), .. })) if before != after => (
+                Severity::Warning,
+                format!(
+                    "DIMM {label} size changed: {} → {}",
+                    format_bytes(*before as f64),
+                    format_bytes(*after as f64)
+                ),
+            ),
+            _ => continue,
+        };
+        changes.push(InventoryChange::new(HardwareGroup::Memory, severity, message));
+    }
+
+    for (address, pair) in paired(&previous.gpus, &current.gpus, |gpu| gpu.address.clone()) {
+        let group = HardwareGroup::Gpu;
+        match pair {
+            (Some(gpu), None) => {
+                changes.push(InventoryChange::new(group, Severity::Warning, format!("GPU missing: {}", describe_gpu(gpu))))
+            }
+            (None, Some(gpu)) => {
+                changes.push(InventoryChange::new(group, Severity::Info, format!("New GPU added: {}", describe_gpu(gpu))))
+            }
+            (Some(before), Some(after)) => {
+                if (&before.vendor, &before.device_id) != (&after.vendor, &after.device_id) {
+                    changes.push(InventoryChange::new(
+                        group,
+                        Severity::Info,
+                        format!("GPU at {address} changed: {} → {}", describe_gpu(before), describe_gpu(after)),
+                    ));
+                }
+                if before.driver != after.driver {
+                    // A GPU without a driver is there but unusable.
+                    let severity = if after.driver.is_none() { Severity::Warning } else { Severity::Info };
+                    changes.push(InventoryChange::new(
+                        group,
+                        severity,
+                        format!(
+                            "GPU driver at {address} changed: {} → {}",
+                            before.driver.as_deref().unwrap_or("none"),
+                            after.driver.as_deref().unwrap_or("none")
+                        ),
+                    ));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // Disks are matched by serial so a renumbered device node is not a new disk.
+    let disk_key = |disk: &DiskInventory| disk.serial.clone().unwrap_or_else(|| format!("name:{}", disk.name));
+    for (_, pair) in paired(&previous.disks, &current.disks, disk_key) {
+        let group = HardwareGroup::Storage;
+        match pair {
+            (Some(disk), None) => {
+                changes.push(InventoryChange::new(group, Severity::Warning, format!("Disk missing: {}", describe_disk(disk))))
+            }
+            (None, Some(disk)) => {
+                changes.push(InventoryChange::new(group, Severity::Info, format!("New disk added: {}", describe_disk(disk))))
+            }
+            (Some(before), Some(after)) => {
+                field(
+                    &mut changes,
+                    group,
+                    &format!("Firmware of {}", describe_disk(after)),
+                    &before.firmware,
+                    &after.firmware,
+                );
+                if before.size != after.size {
+                    changes.push(InventoryChange::new(
+                        group,
+                        Severity::Warning,
+                        format!(
+                            "Disk {} size changed: {} → {}",
+                            describe_disk(after),
+                            format_bytes(before.size as f64),
+                            format_bytes(after.size as f64)
+                        ),
+                    ));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    changes
+}
+
+impl Inventory {
+    pub fn load(path: &Path) -> Result<Self, InventoryError> {
+        let text = fs::read_to_string(path).map_err(|e| InventoryError::Io(path.to_path_buf(), e))?;
+        serde_json::from_str(&text).map_err(|e| InventoryError::Parse(path.to_path_buf(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), InventoryError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| InventoryError::Io(dir.to_path_buf(), e))?;
+        }
+        fs::write(path, self.to_json()).map_err(|e| InventoryError::Io(path.to_path_buf(), e))
+    }
+
+    /// Compares this inventory with the one saved at `path`, if there is one,
+    /// and saves this one in its place. An unreadable old file is reported
+    /// after it has been replaced, so the next run starts clean.
+    pub fn record(&self, path: &Path) -> Result<Vec<InventoryChange>, InventoryError> {
+        let previous = path.exists().then(|| Self::load(path));
+        self.save(path)?;
+        match previous {
+            Some(previous) => Ok(diff(&previous?, self)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// `$HWMON_INVENTORY`, else `hwmon/inventory.json` under the platform state directory.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(INVENTORY_ENV) {
+            return Some(PathBuf::from(path));
+        }
+
+        let state_dir = if cfg!(windows) {
+            std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+        } else {
+            std::env::var_os("XDG_STATE_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+        };
+        state_dir.map(|dir| dir.join("hwmon").join("inventory.json"))
+    }
+}
+
+#[derive(Debug)]
+pub enum InventoryError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::Io(path, e) => write!(f, "could not access {}: {e}", path.display()),
+            InventoryError::Parse(path, e) => write!(f, "invalid inventory in {}: {e}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InventoryError::Io(_, e) => Some(e),
+            InventoryError::Parse(_, e) => Some(e),
+        }
+    }
+}
+
+/// Repeats the changes found at startup as alerts in every snapshot.
+pub struct InventoryAlerts {
+    changes: Vec<InventoryChange>,
+}
+
+impl InventoryAlerts {
+    pub fn new(changes: Vec<InventoryChange>) -> Self {
+        Self { changes }
+    }
+}
+
+impl SensorSource for InventoryAlerts {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        for change in &self.changes {
+            snapshot
+                .alerts
+                .push(Alert::new(change.group, "inventory", change.severity, format!("{change} since the last run")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disk(name: &str, serial: &str) -> DiskInventory {
+        DiskInventory {
+            name: name.to_string(),
+            model: Some("Samsung SSD 980 PRO 1TB".to_string()),
+            serial: Some(serial.to_string()),
+            firmware: Some("5B2QGXA7".to_string()),
+            kind: "NVMe".to_string(),
+            size: 1_000_204_886_016,
+        }
+    }
+
+    fn dimm(label: &str) -> DimmInfo {
+        DimmInfo {
+            label: label.to_string(),
+            size: Some(16 << 30),
+            kind: Some("Unbuffered-DDR4".to_string()),
+        }
+    }
+
+    fn inventory() -> Inventory {
+        Inventory {
+            memory: 32 << 30,
+            dimms: vec![dimm("DIMM_A1"), dimm("DIMM_B1")],
+            disks: vec![disk("nvme0n1", "S5GXNF0R123456")],
+            kernel: Some("6.8.0-45-generic".to_string()),
+            ..Inventory::default()
+        }
+    }
+
+    fn info(group: HardwareGroup, message: &str) -> InventoryChange {
+        InventoryChange::new(group, Severity::Info, message.to_string())
+    }
+
+    fn warning(group: HardwareGroup, message: &str) -> InventoryChange {
+        InventoryChange::new(group, Severity::Warning, message.to_string())
+    }
+
+    #[test]
+    fn new_disk() {
+        let mut current = inventory();
+        current.disks.push(disk("nvme1n1", "S6B0NL0W654321"));
+        assert_eq!(
+            diff(&inventory(), &current),
+            [info(
+                HardwareGroup::Storage,
+                "New disk added: nvme1n1 (Samsung SSD 980 PRO 1TB, serial S6B0NL0W654321)"
+            )]
+        );
+    }
+
+    #[test]
+    fn missing_disk_is_matched_by_serial() {
+        let mut previous = inventory();
+        previous.disks.push(disk("nvme1n1", "S6B0NL0W654321"));
+        // The remaining disk came back as nvme1n1; that is not a new disk.
+        let mut current = inventory();
+        current.disks[0].name = "nvme1n1".to_string();
+        assert_eq!(
+            diff(&previous, &current),
+            [warning(
+                HardwareGroup::Storage,
+                "Disk missing: nvme1n1 (Samsung SSD 980 PRO 1TB, serial S6B0NL0W654321)"
+            )]
+        );
+    }
+
+    #[test]
+    fn bios_version() {
+        let mut previous = inventory();
+        previous.system.bios_version = Some("1.2.0".to_string());
+        let mut current = inventory();
+        current.system.bios_version = Some("1.4.1".to_string());
+        assert_eq!(diff(&previous, &current), [info(HardwareGroup::Board, "BIOS version changed: 1.2.0 → 1.4.1")]);
+    }
+
+    #[test]
+    fn missing_dimm() {
+        let mut current = inventory();
+        current.dimms.remove(1);
+        current.memory = 16 << 30;
+        assert_eq!(
+            diff(&inventory(), &current),
+            [
+                warning(HardwareGroup::Memory, "Memory shrank: 32.0 GiB → 16.0 GiB"),
+                warning(HardwareGroup::Memory, "DIMM missing: DIMM_B1 (16.0 GiB)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn memory_within_tolerance_and_kernel_upgrade_are_not_changes() {
+        let mut current = inventory();
+        current.memory -= (current.memory as f64 * MEMORY_TOLERANCE / 2.0) as u64;
+        current.kernel = Some("6.11.0-8-generic".to_string());
+        assert_eq!(diff(&inventory(), &current), []);
+    }
+
+    #[test]
+    fn microcode_and_flag_updates_are_not_changes() {
+        let mut previous = inventory();
+        previous.cpu.microcode = Some("0xa201016".to_string());
+        previous.cpu.flags = vec!["avx2".to_string(), "sse4_2".to_string()];
+        let mut current = inventory();
+        current.cpu.microcode = Some("0xa201205".to_string());
+        current.cpu.flags = vec!["avx2".to_string(), "ibpb_exit_to_user".to_string(), "sse4_2".to_string()];
+        assert_eq!(diff(&previous, &current), []);
+    }
+
+    #[test]
+    fn missing_dimm_without_edac() {
+        let sensor_dimm = |label: &str| DimmInfo {
+            label: label.to_string(),
+            size: None,
+            kind: None,
+        };
+        let previous = Inventory {
+            dimms: vec![sensor_dimm("DIMM 0 (bus 0, 0x18)"), sensor_dimm("DIMM 2 (bus 0, 0x1a)")],
+            ..Inventory::default()
+        };
+        let current = Inventory {
+            dimms: vec![sensor_dimm("DIMM 0 (bus 0, 0x18)")],
+            ..Inventory::default()
+        };
+        assert_eq!(diff(&previous, &current), [warning(HardwareGroup::Memory, "DIMM missing: DIMM 2 (bus 0, 0x1a)")]);
+    }
+
+    #[test]
+    fn record_reports_changes_since_the_saved_inventory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/inventory.json");
+        let mut upgraded = inventory();
+        upgraded.disks.push(disk("nvme1n1", "S6B0NL0W654321"));
+
+        assert_eq!(inventory().record(&path).unwrap(), []);
+        assert_eq!(Inventory::load(&path).unwrap(), inventory());
+        assert_eq!(
+            upgraded.record(&path).unwrap(),
+            [info(
+                HardwareGroup::Storage,
+                "New disk added: nvme1n1 (Samsung SSD 980 PRO 1TB, serial S6B0NL0W654321)"
+            )]
+        );
+        assert_eq!(upgraded.record(&path).unwrap(), []);
+    }
+
+    #[test]
+    fn unreadable_inventory_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inventory.json");
+        fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(inventory().record(&path), Err(InventoryError::Parse(..))));
+        assert_eq!(inventory().record(&path).unwrap(), []);
+    }
+}
@@ -0,0 +1,58 @@
+//! Sensor collection shared by the egui monitor and any headless tooling.
+//!
+//! Sources implement [`SensorSource`] and write their readings into a
+//! [`Snapshot`]; consumers only ever look at snapshots.
+
+mod alert;
+pub mod cgroup;
+pub mod config;
+pub mod cpu;
+mod cpu_temp;
+pub mod diskstats;
+pub mod edac;
+mod fans;
+pub mod gpu;
+pub mod hwmon;
+pub mod inventory;
+pub mod inventory_history;
+pub mod memory;
+pub mod network;
+pub mod power_supply;
+pub mod powercap;
+pub mod proc_stat;
+pub mod psi;
+mod sampler;
+mod sensor;
+pub mod smart;
+mod snapshot;
+mod source;
+pub mod storage;
+mod sysfs;
+mod sysinfo_source;
+pub mod thermal;
+
+pub use alert::{Alert, Severity};
+pub use cgroup::{Cgroup, CgroupSource};
+pub use config::Config;
+pub use cpu::{CpuSource, LogicalCpu};
+pub use cpu_temp::{select_cpu_temperature, CpuTempSelector, CpuTemperature};
+pub use edac::EdacSource;
+pub use fans::FanStallDetector;
+pub use gpu::{DrmGpuSource, NvidiaSmiSource};
+pub use hwmon::HwmonSource;
+pub use inventory::{Inventory, InventoryReader};
+pub use inventory_history::{InventoryAlerts, InventoryChange};
+pub use memory::MemorySource;
+pub use network::{NetInterface, NetworkSource};
+pub use power_supply::{Battery, PowerSupplySource};
+pub use powercap::PowercapSource;
+pub use proc_stat::ProcStatSource;
+pub use psi::PsiSource;
+pub use sampler::{Sampler, DEFAULT_SAMPLE_INTERVAL};
+pub use sensor::{format_bytes, HardwareGroup, Sensor, SensorId, SensorKind, Unit};
+pub use smart::{SmartHealth, SmartSource};
+pub use snapshot::{DiskInfo, ProcessInfo, Snapshot};
+pub use source::{Collector, SensorSource};
+pub use storage::{Drive, DriveKind, StorageSource};
+pub use sysinfo_source::SysinfoSource;
+pub use thermal::ThermalSource;
@@ -0,0 +1,237 @@
+//! DIMM temperatures and memory usage.
+//!
+//! DDR4 modules with a thermal sensor show up through the `jc42` hwmon
+//! driver, DDR5 modules through `spd5118`. Both sit on the SMBus, and the
+//! I2C address tells us which slot a module is in.
+
+use std::path::{Path, PathBuf};
+
+use crate::hwmon::{read_chips, ChannelType, HwmonChip, DEFAULT_HWMON_ROOT};
+use crate::sysfs::read_string;
+use crate::{HardwareGroup, Sensor, SensorKind, SensorSource, Snapshot};
+
+pub const DEFAULT_MEMINFO: &str = "/proc/meminfo";
+
+const DIMM_DRIVERS: &[&str] = &["jc42", "spd5118"];
+
+/// The fields of `/proc/meminfo` we show, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+impl MemInfo {
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+}
+
+/// Parses `/proc/meminfo`; missing fields are left at zero.
+pub fn parse_meminfo(text: &str) -> MemInfo {
+    let mut info = MemInfo::default();
+    for line in text.lines() {
+        let Some((key, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let mut parts = rest.split_whitespace();
+        let Some(Ok(value)) = parts.next().map(str::parse::<u64>) else {
+            continue;
+        };
+        let bytes = if parts.next() == Some("kB") { value * 1024 } else { value };
+
+        let field = match key {
+            "MemTotal" => &mut info.total,
+            "MemFree" => &mut info.free,
+            "MemAvailable" => &mut info.available,
+            "Buffers" => &mut info.buffers,
+            "Cached" => &mut info.cached,
+            "SwapTotal" => &mut info.swap_total,
+            "SwapFree" => &mut info.swap_free,
+            _ => continue,
+        };
+        *field = bytes;
+    }
+    info
+}
+
+/// Describes a DIMM from its sensor's I2C device name, e.g. `0-0018`.
+///
+/// jc42 thermal sensors live at 0x18-0x1f and SPD5118 hubs at 0x50-0x57,
+/// one address per slot on the bus.
+pub fn dimm_label(driver: &str, i2c_device: &str) -> Option<String> {
+    let (bus, address) = i2c_device.split_once('-')?;
+    let address = u16::from_str_radix(address, 16).ok()?;
+    let base = match driver {
+        "jc42" => 0x18,
+        "spd5118" => 0x50,
+        _ => return None,
+    };
+    let slot = address.checked_sub(base).filter(|slot| *slot < 8)?;
+    Some(format!("DIMM {slot} (bus {bus}, 0x{address:02x})"))
+}
+
+/// jc42 and spd5118 chips below `hwmon_root`, each with its slot label.
+pub fn dimm_chips(hwmon_root: &Path) -> Vec<(String, HwmonChip)> {
+    read_chips(hwmon_root)
+        .into_iter()
+        .filter(|chip| DIMM_DRIVERS.contains(&chip.name.as_str()))
+        .map(|chip| {
+            let device = chip.device_name();
+            let label = device
+                .as_deref()
+                .and_then(|device| dimm_label(&chip.name, device))
+                .or(device)
+                .unwrap_or_else(|| chip.name.clone());
+            (label, chip)
+        })
+        .collect()
+}
+
+pub struct MemorySource {
+    hwmon_root: PathBuf,
+    meminfo: PathBuf,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self {
+            hwmon_root: DEFAULT_HWMON_ROOT.into(),
+            meminfo: DEFAULT_MEMINFO.into(),
+        }
+    }
+
+    pub fn with_hwmon_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.hwmon_root = root.into();
+        self
+    }
+
+    pub fn with_meminfo(mut self, path: impl Into<PathBuf>) -> Self {
+        self.meminfo = path.into();
+        self
+    }
+
+    fn dimm_sensors(&self) -> Vec<Sensor> {
+        let mut sensors = Vec::new();
+        for (label, chip) in dimm_chips(&self.hwmon_root) {
+            for channel in chip.channels.iter().filter(|c| c.channel_type == ChannelType::Temp) {
+                let mut sensor = chip.sensor(channel);
+                sensor.label = label.clone();
+                sensors.push(sensor);
+            }
+        }
+
+        if let Some(hottest) = sensors.iter().map(|sensor| sensor.value).reduce(f64::max) {
+            sensors.insert(
+                0,
+                Sensor::new(
+                    "memory/temperature_max",
+                    HardwareGroup::Memory,
+                    "dimm",
+                    "Memory Temperature (hottest DIMM)",
+                    SensorKind::Temperature,
+                    hottest,
+                ),
+            );
+        }
+        sensors
+    }
+
+    fn usage_sensors(&self) -> Vec<Sensor> {
+        let Some(text) = read_string(&self.meminfo) else {
+            return Vec::new();
+        };
+        let info = parse_meminfo(&text);
+        if info.total == 0 {
+            return Vec::new();
+        }
+
+        let sensor = |name: &str, label: &str, kind: SensorKind, value: f64| {
+            Sensor::new(format!("memory/{name}"), HardwareGroup::Memory, "meminfo", label, kind, value)
+        };
+        let total = Some(info.total as f64);
+
+        let mut sensors = vec![
+            sensor("load", "Memory Load", SensorKind::Load, info.used() as f64 / info.total as f64 * 100.0),
+            sensor("used", "Memory Used", SensorKind::Data, info.used() as f64).with_max(total),
+            sensor("available", "Memory Available", SensorKind::Data, info.available as f64).with_max(total),
+            sensor("cached", "Cached", SensorKind::Data, (info.cached + info.buffers) as f64).with_max(total),
+        ];
+        if info.swap_total > 0 {
+            sensors.push(
+                sensor("swap_used", "Swap Used", SensorKind::Data, info.swap_used() as f64)
+                    .with_max(Some(info.swap_total as f64)),
+            );
+        }
+        sensors
+    }
+}
+
+impl Default for MemorySource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for MemorySource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        snapshot.sensors.extend(self.dimm_sensors());
+        snapshot.sensors.extend(self.usage_sensors());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_meminfo_in_bytes() {
+        let info = parse_meminfo(
+            "MemTotal:       32768000 kB\n\
+             MemFree:         1024000 kB\n\
+             MemAvailable:   20480000 kB\n\
+             Buffers:          512000 kB\n\
+             Cached:         12288000 kB\n\
+             SwapCached:         1000 kB\n\
+             SwapTotal:       8192000 kB\n\
+             SwapFree:        8000000 kB\n\
+             HugePages_Total:       0\n",
+        );
+        assert_eq!(
+            info,
+            MemInfo {
+                total: 32_768_000 * 1024,
+                free: 1_024_000 * 1024,
+                available: 20_480_000 * 1024,
+                buffers: 512_000 * 1024,
+                cached: 12_288_000 * 1024,
+                swap_total: 8_192_000 * 1024,
+                swap_free: 8_000_000 * 1024,
+            }
+        );
+        // Used memory is what is not available, not what is not free.
+        assert_eq!(info.used(), 12_288_000 * 1024);
+        assert_eq!(info.swap_used(), 192_000 * 1024);
+    }
+
+    #[test]
+    fn labels_dimms_by_sensor_address() {
+        assert_eq!(dimm_label("jc42", "0-0018").as_deref(), Some("DIMM 0 (bus 0, 0x18)"));
+        assert_eq!(dimm_label("jc42", "3-001b").as_deref(), Some("DIMM 3 (bus 3, 0x1b)"));
+        assert_eq!(dimm_label("spd5118", "1-0057").as_deref(), Some("DIMM 7 (bus 1, 0x57)"));
+        // Outside the driver's address range, or not an I2C name at all.
+        assert_eq!(dimm_label("jc42", "0-0050"), None);
+        assert_eq!(dimm_label("spd5118", "0-0018"), None);
+        assert_eq!(dimm_label("nct6775", "0-0018"), None);
+        assert_eq!(dimm_label("jc42", "nct6775.656"), None);
+    }
+}
@@ -0,0 +1,269 @@
+//! Network interfaces from `/sys/class/net`.
+//!
+//! Every interface has cumulative counters under `statistics/`; rates are
+//! their difference between two samples. Physical NICs have a `device`
+//! link, while loopback, veth pairs, bridges, tunnels and the like live under
+//! `/sys/devices/virtual/net` and have none.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::sysfs::{read_string, read_value};
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_NET_ROOT: &str = "/sys/class/net";
+
+/// `type` of the loopback device (`ARPHRD_LOOPBACK`).
+const ARPHRD_LOOPBACK: u32 = 772;
+
+/// Cumulative counters from `statistics/`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+impl InterfaceCounters {
+    fn read(path: &Path) -> Self {
+        let counter = |name: &str| read_value(path.join("statistics").join(name)).unwrap_or(0);
+        Self {
+            rx_bytes: counter("rx_bytes"),
+            tx_bytes: counter("tx_bytes"),
+            rx_packets: counter("rx_packets"),
+            tx_packets: counter("tx_packets"),
+            rx_errors: counter("rx_errors"),
+            tx_errors: counter("tx_errors"),
+            rx_dropped: counter("rx_dropped"),
+            tx_dropped: counter("tx_dropped"),
+        }
+    }
+
+    /// Each counter with the name used in sensor ids and its label.
+    fn fields(&self) -> [(&'static str, &'static str, u64); 8] {
+        [
+            ("rx_bytes", "Receive", self.rx_bytes),
+            ("tx_bytes", "Send", self.tx_bytes),
+            ("rx_packets", "Packets Received", self.rx_packets),
+            ("tx_packets", "Packets Sent", self.tx_packets),
+            ("rx_errors", "Receive Errors", self.rx_errors),
+            ("tx_errors", "Send Errors", self.tx_errors),
+            ("rx_dropped", "Receive Drops", self.rx_dropped),
+            ("tx_dropped", "Send Drops", self.tx_dropped),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetInterface {
+    pub name: String,
+    /// `up`, `down`, `dormant`, `unknown` (common for tunnels and loopback), ...
+    pub operstate: String,
+    /// Negotiated link speed in Mbit/s; unknown while the link is down.
+    pub speed: Option<u64>,
+    pub mtu: Option<u32>,
+    pub loopback: bool,
+    /// No backing hardware device: veth, bridges, tunnels, loopback.
+    pub is_virtual: bool,
+    pub counters: InterfaceCounters,
+}
+
+impl NetInterface {
+    pub fn read(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_string();
+        Some(Self {
+            operstate: read_string(path.join("operstate")).unwrap_or_else(|| "unknown".to_string()),
+            // Reads fail or give -1 when the link is down.
+            speed: read_value::<i64>(path.join("speed")).filter(|speed| *speed > 0).map(|speed| speed as u64),
+            mtu: read_value(path.join("mtu")),
+            loopback: read_value::<u32>(path.join("type")) == Some(ARPHRD_LOOPBACK),
+            is_virtual: !path.join("device").exists(),
+            counters: InterfaceCounters::read(path),
+            name,
+        })
+    }
+}
+
+/// Which interfaces [`NetworkSource`] reports.
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceFilter {
+    pub show_loopback: bool,
+    pub show_virtual: bool,
+    /// Names to leave out; a trailing `*` matches any suffix, as in `veth*`.
+    pub ignore: Vec<String>,
+}
+
+impl InterfaceFilter {
+    pub fn shows(&self, interface: &NetInterface) -> bool {
+        if interface.loopback {
+            return self.show_loopback;
+        }
+        if interface.is_virtual && !self.show_virtual {
+            return false;
+        }
+        !self.ignore.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => interface.name.starts_with(prefix),
+            None => interface.name == *pattern,
+        })
+    }
+}
+
+/// Publishes per-interface rates and the interface list in
+/// [`Snapshot::interfaces`].
+pub struct NetworkSource {
+    root: PathBuf,
+    filter: InterfaceFilter,
+    /// Counters from the previous sample, by interface; interfaces that
+    /// went away are dropped on the next sample.
+    previous: HashMap<String, (InterfaceCounters, Instant)>,
+}
+
+impl NetworkSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_NET_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/class/net`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            filter: InterfaceFilter::default(),
+            previous: HashMap::new(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: InterfaceFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Every interface, sorted by name, before filtering.
+    pub fn interfaces(&self) -> Vec<NetInterface> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut interfaces: Vec<NetInterface> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| NetInterface::read(&entry.path()))
+            .collect();
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        interfaces
+    }
+
+    /// [`collect`](SensorSource::collect) with the counters read at `now`.
+    fn collect_at(&mut self, snapshot: &mut Snapshot, now: Instant) {
+        let interfaces: Vec<NetInterface> = self
+            .interfaces()
+            .into_iter()
+            .filter(|interface| self.filter.shows(interface))
+            .collect();
+        let mut previous = std::mem::take(&mut self.previous);
+
+        for interface in &interfaces {
+            let name = interface.name.as_str();
+            self.previous.insert(name.to_string(), (interface.counters, now));
+            let Some((previous, read_at)) = previous.remove(name) else {
+                continue;
+            };
+            let elapsed = now.duration_since(read_at).as_secs_f64();
+            if elapsed <= 0.0 {
+                continue;
+            }
+
+            let mut errors = 0;
+            for ((field, label, now), (_, _, before)) in interface.counters.fields().into_iter().zip(previous.fields()) {
+                // Counters restart from zero when a driver is reloaded.
+                let delta = now.saturating_sub(before);
+                let kind = if field.ends_with("_bytes") { SensorKind::Throughput } else { SensorKind::Rate };
+                if field.ends_with("_errors") {
+                    errors += delta;
+                }
+                snapshot.sensors.push(Sensor::new(
+                    format!("net/{name}/{field}"),
+                    HardwareGroup::Network,
+                    name,
+                    label,
+                    kind,
+                    delta as f64 / elapsed,
+                ));
+            }
+            if errors > 0 {
+                snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Network,
+                    name,
+                    Severity::Warning,
+                    format!("{errors} packet errors since the last sample"),
+                ));
+            }
+        }
+
+        snapshot.interfaces = interfaces;
+    }
+}
+
+impl Default for NetworkSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for NetworkSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        self.collect_at(snapshot, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    #[test]
+    fn forgets_interfaces_that_went_away() {
+        let root = tempfile::tempdir().unwrap();
+        let filter = InterfaceFilter {
+            show_virtual: true,
+            ..InterfaceFilter::default()
+        };
+        let mut source = NetworkSource::with_root(root.path()).with_filter(filter);
+        let start = Instant::now();
+        let sample = |source: &mut NetworkSource, seconds: u64| {
+            let mut snapshot = Snapshot::default();
+            source.collect_at(&mut snapshot, start + std::time::Duration::from_secs(seconds));
+            snapshot
+        };
+        let rate = |snapshot: &Snapshot, interface: &str| {
+            snapshot.sensor(&format!("net/{interface}/rx_bytes").into()).map(|sensor| sensor.value)
+        };
+
+        write_tree(
+            root.path(),
+            &[
+                ("eth0/statistics/rx_bytes", "1000\n"),
+                ("veth1a2b3c/statistics/rx_bytes", "0\n"),
+            ],
+        );
+        sample(&mut source, 0);
+
+        std::fs::remove_dir_all(root.path().join("veth1a2b3c")).unwrap();
+        write_tree(root.path(), &[("eth0/statistics/rx_bytes", "3000\n")]);
+        let snapshot = sample(&mut source, 1);
+        assert_eq!(rate(&snapshot, "eth0"), Some(2000.0));
+        assert_eq!(rate(&snapshot, "veth1a2b3c"), None);
+
+        // A container's veth coming back under the same name starts over
+        // instead of reporting everything since it went away as one interval.
+        write_tree(root.path(), &[("veth1a2b3c/statistics/rx_bytes", "5000\n")]);
+        let snapshot = sample(&mut source, 2);
+        assert_eq!(rate(&snapshot, "eth0"), Some(0.0));
+        assert_eq!(rate(&snapshot, "veth1a2b3c"), None);
+        assert_eq!(rate(&sample(&mut source, 3), "veth1a2b3c"), Some(0.0));
+    }
+}
@@ -0,0 +1,259 @@
+//! Batteries and AC adapters from `/sys/class/power_supply`.
+//!
+//! Batteries report either energy (`energy_*` in µWh, `power_now` in µW) or
+//! charge (`charge_*` in µAh, `current_now` in µA), depending on the firmware;
+//! charge is turned into energy with the design voltage so both look the
+//! same. Adapters (`type` `Mains` or `USB`) only say whether they are online.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::sysfs::{read_string, read_value};
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+
+/// Charge below which a discharging battery raises an alert, in percent.
+const LOW_BATTERY: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Battery {
+    /// Kernel name, e.g. `BAT0`.
+    pub name: String,
+    pub model: Option<String>,
+    /// `Charging`, `Discharging`, `Full`, `Not charging` or `Unknown`.
+    pub status: String,
+    /// Charge in percent.
+    pub capacity: Option<f64>,
+    /// Watt-hours now, when full and when new.
+    pub energy_now: Option<f64>,
+    pub energy_full: Option<f64>,
+    pub energy_full_design: Option<f64>,
+    /// Watts flowing in or out; always positive.
+    pub power: Option<f64>,
+    pub voltage: Option<f64>,
+    pub cycle_count: Option<u64>,
+}
+
+impl Battery {
+    fn read(path: &Path, name: String) -> Self {
+        let micro = |name: &str| read_value::<f64>(path.join(name)).map(|value| value / 1e6);
+        let voltage = micro("voltage_now");
+        let design_voltage = micro("voltage_min_design").or(voltage);
+
+        // µAh times volts gives µWh.
+        let energy = |name: &str| {
+            micro(&format!("energy_{name}")).or_else(|| Some(micro(&format!("charge_{name}"))? * design_voltage?))
+        };
+        let power = micro("power_now").or_else(|| Some(micro("current_now")? * voltage?)).map(f64::abs);
+
+        let energy_now = energy("now");
+        let energy_full = energy("full");
+        Self {
+            model: read_string(path.join("model_name")).filter(|model| !model.is_empty()),
+            status: read_string(path.join("status")).unwrap_or_else(|| "Unknown".to_string()),
+            capacity: read_value(path.join("capacity")).or_else(|| Some(energy_now? / energy_full? * 100.0)),
+            energy_now,
+            energy_full,
+            energy_full_design: energy("full_design"),
+            power,
+            voltage,
+            // Many batteries report 0 when they do not track cycles.
+            cycle_count: read_value(path.join("cycle_count")).filter(|count| *count > 0),
+            name,
+        }
+    }
+
+    /// Full charge capacity compared to the design capacity, in percent.
+    pub fn health(&self) -> Option<f64> {
+        let design = self.energy_full_design.filter(|design| *design > 0.0)?;
+        Some(self.energy_full? / design * 100.0)
+    }
+
+    /// Time until empty while discharging, or until full while charging, at
+    /// the current power draw.
+    pub fn time_remaining(&self) -> Option<Duration> {
+        let power = self.power.filter(|power| *power > 0.0)?;
+        let energy = match self.status.as_str() {
+            "Discharging" => self.energy_now?,
+            "Charging" => self.energy_full? - self.energy_now?,
+            _ => return None,
+        };
+        Some(Duration::from_secs_f64((energy / power).max(0.0) * 3600.0))
+    }
+}
+
+/// Publishes battery readings in the Battery group, the batteries in
+/// [`Snapshot::batteries`] and the adapter state in [`Snapshot::ac_online`].
+/// Desktops simply end up with neither.
+pub struct PowerSupplySource {
+    root: PathBuf,
+}
+
+impl PowerSupplySource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_POWER_SUPPLY_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/class/power_supply`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Default for PowerSupplySource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for PowerSupplySource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return;
+        };
+        let mut supplies: Vec<(String, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .map(|entry| (entry.file_name().to_string_lossy().into_owned(), entry.path()))
+            .collect();
+        supplies.sort();
+
+        for (name, path) in supplies {
+            match read_string(path.join("type")).as_deref() {
+                Some("Mains") | Some("USB") => {
+                    if let Some(online) = read_value::<u8>(path.join("online")) {
+                        snapshot.ac_online = Some(snapshot.ac_online.unwrap_or(false) || online == 1);
+                        snapshot.sensors.push(Sensor::new(
+                            format!("power_supply/{name}/online"),
+                            HardwareGroup::Battery,
+                            name.as_str(),
+                            "AC Adapter Online",
+                            SensorKind::Count,
+                            f64::from(online),
+                        ));
+                    }
+                }
+                // Peripherals such as wireless mice report scope `Device`.
+                Some("Battery") if read_string(path.join("scope")).as_deref() != Some("Device") => {
+                    let battery = Battery::read(&path, name);
+                    push_sensors(snapshot, &battery);
+                    snapshot.batteries.push(battery);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn push_sensors(snapshot: &mut Snapshot, battery: &Battery) {
+    let sensor = |name: &str, label: &str, kind: SensorKind, value: f64| {
+        Sensor::new(
+            format!("power_supply/{}/{name}", battery.name),
+            HardwareGroup::Battery,
+            battery.name.as_str(),
+            label,
+            kind,
+            value,
+        )
+    };
+
+    if let Some(capacity) = battery.capacity {
+        snapshot.sensors.push(sensor("capacity", "Charge", SensorKind::Load, capacity));
+        if capacity <= LOW_BATTERY && battery.status == "Discharging" {
+            snapshot.alerts.push(Alert::new(
+                HardwareGroup::Battery,
+                battery.name.as_str(),
+                Severity::Warning,
+                format!("Battery low ({capacity:.0}%)"),
+            ));
+        }
+    }
+    if let Some(energy) = battery.energy_now {
+        snapshot.sensors.push(sensor("energy", "Energy", SensorKind::Energy, energy).with_max(battery.energy_full));
+    }
+    if let Some(power) = battery.power {
+        snapshot.sensors.push(sensor("power", "Power Draw", SensorKind::Power, power));
+    }
+    if let Some(voltage) = battery.voltage {
+        snapshot.sensors.push(sensor("voltage", "Voltage", SensorKind::Voltage, voltage));
+    }
+    if let Some(health) = battery.health() {
+        snapshot.sensors.push(sensor("health", "Health (full vs design)", SensorKind::Load, health));
+    }
+    if let Some(cycles) = battery.cycle_count {
+        snapshot.sensors.push(sensor("cycle_count", "Charge Cycles", SensorKind::Count, cycles as f64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    fn battery(status: &str, energy_now: f64, energy_full: f64, power: f64) -> Battery {
+        Battery {
+            name: "BAT0".to_string(),
+            model: None,
+            status: status.to_string(),
+            capacity: None,
+            energy_now: Some(energy_now),
+            energy_full: Some(energy_full),
+            energy_full_design: None,
+            power: Some(power),
+            voltage: None,
+            cycle_count: None,
+        }
+    }
+
+    #[test]
+    fn converts_charge_to_energy() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("AC/type", "Mains\n"),
+                ("AC/online", "0\n"),
+                ("BAT0/type", "Battery\n"),
+                ("BAT0/status", "Discharging\n"),
+                ("BAT0/voltage_min_design", "11400000\n"),
+                ("BAT0/voltage_now", "12000000\n"),
+                ("BAT0/charge_now", "2000000\n"),
+                ("BAT0/charge_full", "4000000\n"),
+                ("BAT0/charge_full_design", "5000000\n"),
+                ("BAT0/current_now", "1500000\n"),
+                ("BAT0/cycle_count", "312\n"),
+            ],
+        );
+        let mut snapshot = Snapshot::default();
+        PowerSupplySource::with_root(root.path()).collect(&mut snapshot);
+
+        let battery = &snapshot.batteries[0];
+        let close = |value: Option<f64>, expected: f64| (value.unwrap() - expected).abs() < 1e-9;
+        // Charge is scaled by the design voltage, current by the present one.
+        assert!(close(battery.energy_now, 22.8));
+        assert!(close(battery.energy_full, 45.6));
+        assert!(close(battery.energy_full_design, 57.0));
+        assert!(close(battery.power, 18.0));
+        assert!(close(battery.capacity, 50.0));
+        assert!(close(battery.health(), 80.0));
+
+        assert_eq!(snapshot.ac_online, Some(false));
+        assert_eq!(snapshot.sensor(&"power_supply/AC/online".into()).unwrap().value, 0.0);
+        assert_eq!(snapshot.sensor(&"power_supply/BAT0/cycle_count".into()).unwrap().value, 312.0);
+    }
+
+    #[test]
+    fn time_remaining_follows_the_status() {
+        assert_eq!(
+            battery("Discharging", 30.0, 50.0, 15.0).time_remaining(),
+            Some(Duration::from_secs(2 * 3600))
+        );
+        assert_eq!(
+            battery("Charging", 30.0, 50.0, 40.0).time_remaining(),
+            Some(Duration::from_secs(30 * 60))
+        );
+        assert_eq!(battery("Full", 50.0, 50.0, 1.0).time_remaining(), None);
+        assert_eq!(battery("Discharging", 30.0, 50.0, 0.0).time_remaining(), None);
+    }
+}
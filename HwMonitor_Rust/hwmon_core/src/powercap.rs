@@ -0,0 +1,269 @@
+//! CPU package and DRAM power from the powercap RAPL energy counters
+//! (`/sys/class/powercap/intel-rapl:*`), which AMD Zen CPUs expose too.
+//!
+//! Each zone has an `energy_uj` counter in microjoules that wraps around at
+//! `max_energy_range_uj`; power is the difference between two samples over
+//! the time between them. Package zones are `intel-rapl:N`, their core,
+//! uncore and DRAM subzones `intel-rapl:N:M`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::sysfs::{read_string, read_value};
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_POWERCAP_ROOT: &str = "/sys/class/powercap";
+
+const ZONE_PREFIX: &str = "intel-rapl:";
+
+/// Microjoules used between two readings of a counter that wraps at
+/// `max_range`.
+///
+/// A counter lower than before has wrapped once; the sample interval is far
+/// shorter than the minutes it takes to wrap twice.
+pub fn energy_delta(previous: u64, current: u64, max_range: u64) -> u64 {
+    if current >= previous {
+        current - previous
+    } else {
+        max_range.saturating_sub(previous) + current
+    }
+}
+
+/// One RAPL zone, e.g. `intel-rapl:0:2` named `dram`.
+#[derive(Debug, Clone)]
+pub struct RaplZone {
+    /// Directory name, which stays the same across reboots.
+    pub id: String,
+    /// Zone name from the `name` attribute: `package-0`, `core`, `uncore`, `dram` or `psys`.
+    pub name: String,
+    pub package: u32,
+    pub path: PathBuf,
+}
+
+impl RaplZone {
+    /// Zones directly below `root`, packages before their subzones.
+    pub fn read_all(root: &Path) -> Vec<RaplZone> {
+        let Ok(entries) = fs::read_dir(root) else {
+            return Vec::new();
+        };
+
+        let mut zones: Vec<(Vec<u32>, RaplZone)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let id = entry.file_name().to_str()?.to_string();
+                let indices: Vec<u32> = id
+                    .strip_prefix(ZONE_PREFIX)?
+                    .split(':')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .ok()?;
+                let zone = RaplZone {
+                    name: read_string(entry.path().join("name"))?,
+                    package: indices[0],
+                    path: entry.path(),
+                    id,
+                };
+                Some((indices, zone))
+            })
+            .collect();
+        zones.sort_by(|a, b| a.0.cmp(&b.0));
+        zones.into_iter().map(|(_, zone)| zone).collect()
+    }
+
+    pub fn group(&self) -> HardwareGroup {
+        match self.name.as_str() {
+            "dram" => HardwareGroup::Memory,
+            "psys" => HardwareGroup::Power,
+            _ => HardwareGroup::Cpu,
+        }
+    }
+
+    /// Human name such as `Package 0` or `DRAM (package 1)`; the package is
+    /// only spelled out when there is more than one.
+    pub fn label(&self, packages: usize) -> String {
+        let name = match self.name.as_str() {
+            "core" => "Cores",
+            "uncore" => "Uncore",
+            "dram" => "DRAM",
+            "psys" => "Platform",
+            name => return name.replace("package-", "Package "),
+        };
+        if packages > 1 {
+            format!("{name} (package {})", self.package)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+struct Counter {
+    energy: u64,
+    read_at: Instant,
+    /// Microjoules used since the source started.
+    total: u64,
+}
+
+/// Power and energy use per RAPL zone.
+pub struct PowercapSource {
+    root: PathBuf,
+    counters: HashMap<String, Counter>,
+}
+
+impl PowercapSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_POWERCAP_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/class/powercap`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            counters: HashMap::new(),
+        }
+    }
+
+    /// [`collect`](SensorSource::collect) with the counters read at `now`.
+    fn collect_at(&mut self, snapshot: &mut Snapshot, now: Instant) {
+        let zones = RaplZone::read_all(&self.root);
+        let packages = zones.iter().filter(|zone| zone.name.starts_with("package-")).count();
+        let mut readable = false;
+
+        for zone in &zones {
+            // Readable by root only since the PLATYPUS side channel fix.
+            let Some(energy) = read_value::<u64>(zone.path.join("energy_uj")) else {
+                continue;
+            };
+            readable = true;
+            let max_range = read_value::<u64>(zone.path.join("max_energy_range_uj"));
+            let label = zone.label(packages);
+            let sensor = |name: &str, label: String, kind: SensorKind, value: f64| {
+                Sensor::new(format!("powercap/{}/{name}", zone.id), zone.group(), "rapl", label, kind, value)
+            };
+
+            let counter = self.counters.entry(zone.id.clone()).or_insert(Counter {
+                energy,
+                read_at: now,
+                total: 0,
+            });
+            let elapsed = now.duration_since(counter.read_at).as_secs_f64();
+            // Without the range a wrap cannot be told apart from a reset, so
+            // that sample is skipped.
+            let delta = match max_range {
+                Some(max_range) => Some(energy_delta(counter.energy, energy, max_range)),
+                None => energy.checked_sub(counter.energy),
+            };
+            if let (Some(delta), true) = (delta, elapsed > 0.0) {
+                counter.total += delta;
+                snapshot.sensors.push(sensor(
+                    "power",
+                    format!("{label} Power"),
+                    SensorKind::Power,
+                    delta as f64 / 1e6 / elapsed,
+                ));
+            }
+            counter.energy = energy;
+            counter.read_at = now;
+
+            // Microjoules to watt-hours.
+            snapshot.sensors.push(sensor(
+                "energy",
+                format!("{label} Energy"),
+                SensorKind::Energy,
+                counter.total as f64 / 3.6e9,
+            ));
+        }
+
+        if !zones.is_empty() && !readable {
+            snapshot.alerts.push(Alert::new(
+                HardwareGroup::Cpu,
+                "rapl",
+                Severity::Info,
+                "RAPL energy counters are only readable by root; power readings unavailable",
+            ));
+        }
+    }
+}
+
+impl Default for PowercapSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for PowercapSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        self.collect_at(snapshot, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+    use std::time::Duration;
+
+    #[test]
+    fn delta_without_wrap() {
+        assert_eq!(energy_delta(1_000_000, 3_500_000, 262_143_328_850), 2_500_000);
+    }
+
+    #[test]
+    fn delta_across_wrap() {
+        let max_range = 262_143_328_850;
+        assert_eq!(energy_delta(max_range - 1_000, 4_000, max_range), 5_000);
+    }
+
+    #[test]
+    fn delta_of_unchanged_counter() {
+        assert_eq!(energy_delta(42, 42, 262_143_328_850), 0);
+    }
+
+    #[test]
+    fn power_from_two_samples() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                ("intel-rapl:0/name", "package-0\n"),
+                ("intel-rapl:0/energy_uj", "262143000000\n"),
+                ("intel-rapl:0/max_energy_range_uj", "262143328850\n"),
+            ],
+        );
+        let mut source = PowercapSource::with_root(root.path());
+        let start = Instant::now();
+        let mut first = Snapshot::default();
+        source.collect_at(&mut first, start);
+        assert!(first.sensor(&"powercap/intel-rapl:0/power".into()).is_none());
+
+        // 4 J over 2 s, across the wrap.
+        write_tree(root.path(), &[("intel-rapl:0/energy_uj", "3671150\n")]);
+        let mut second = Snapshot::default();
+        source.collect_at(&mut second, start + Duration::from_secs(2));
+
+        let power = second.sensor(&"powercap/intel-rapl:0/power".into()).unwrap();
+        assert_eq!(power.label, "Package 0 Power");
+        assert!((power.value - 2.0).abs() < 1e-9, "{} W", power.value);
+        let energy = second.sensor(&"powercap/intel-rapl:0/energy".into()).unwrap();
+        assert!((energy.value - 4.0 / 3600.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn skips_a_wrap_without_the_range() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(root.path(), &[("intel-rapl:0/name", "package-0\n"), ("intel-rapl:0/energy_uj", "9000000\n")]);
+        let mut source = PowercapSource::with_root(root.path());
+        let start = Instant::now();
+        let power_at = |source: &mut PowercapSource, energy: &str, seconds: u64| {
+            write_tree(root.path(), &[("intel-rapl:0/energy_uj", energy)]);
+            let mut snapshot = Snapshot::default();
+            source.collect_at(&mut snapshot, start + Duration::from_secs(seconds));
+            snapshot.sensor(&"powercap/intel-rapl:0/power".into()).map(|sensor| sensor.value)
+        };
+
+        assert_eq!(power_at(&mut source, "9000000\n", 0), None);
+        assert_eq!(power_at(&mut source, "1000000\n", 1), None);
+        assert_eq!(power_at(&mut source, "4000000\n", 2), Some(3.0));
+    }
+}
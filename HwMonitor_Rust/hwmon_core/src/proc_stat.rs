@@ -0,0 +1,278 @@
+//! Where CPU time goes, from the `cpu` lines of `/proc/stat`.
+//!
+//! The kernel keeps cumulative counters in clock ticks per CPU and summed
+//! over all of them; a breakdown is the share of each counter in the ticks
+//! that passed between two samples. Guest time is already counted in user
+//! (and guest_nice in nice), so it is taken out of those here and reported
+//! on its own to make the shares add up to 100%.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::sysfs::read_string;
+use crate::{HardwareGroup, Sensor, SensorKind, SensorSource, Snapshot};
+
+pub const DEFAULT_PROC_STAT: &str = "/proc/stat";
+
+/// Cumulative ticks of one `cpu` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+    pub guest: u64,
+    pub guest_nice: u64,
+}
+
+impl CpuTimes {
+    /// Percentages of the time between `previous` and `self`; `None` if no
+    /// ticks passed.
+    ///
+    /// A counter lower than before (per-CPU iowait can go backwards, and
+    /// everything restarts when a CPU comes back online) counts as zero. The
+    /// total is the sum of the per-state ticks, so the shares always add up
+    /// to 100%.
+    pub fn breakdown_since(&self, previous: &CpuTimes) -> Option<CpuBreakdown> {
+        let ticks = |now: u64, before: u64| now.saturating_sub(before);
+        let user = ticks(self.user, previous.user);
+        let nice = ticks(self.nice, previous.nice);
+        // Guest time is part of user, and guest_nice of nice, so neither can be larger.
+        let guest = ticks(self.guest, previous.guest).min(user);
+        let guest_nice = ticks(self.guest_nice, previous.guest_nice).min(nice);
+        let system = ticks(self.system, previous.system);
+        let idle = ticks(self.idle, previous.idle);
+        let iowait = ticks(self.iowait, previous.iowait);
+        let irq = ticks(self.irq, previous.irq);
+        let softirq = ticks(self.softirq, previous.softirq);
+        let steal = ticks(self.steal, previous.steal);
+
+        let total = user + nice + system + idle + iowait + irq + softirq + steal;
+        if total == 0 {
+            return None;
+        }
+        let share = |ticks: u64| ticks as f64 / total as f64 * 100.0;
+        Some(CpuBreakdown {
+            user: share(user - guest),
+            nice: share(nice - guest_nice),
+            system: share(system),
+            idle: share(idle),
+            iowait: share(iowait),
+            irq: share(irq),
+            softirq: share(softirq),
+            steal: share(steal),
+            guest: share(guest),
+            guest_nice: share(guest_nice),
+        })
+    }
+}
+
+/// Share of CPU time per state over one interval, in percent. `user` and
+/// `nice` exclude the time spent running guests, which is in `guest` and
+/// `guest_nice`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuBreakdown {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub idle: f64,
+    pub iowait: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    pub steal: f64,
+    pub guest: f64,
+    pub guest_nice: f64,
+}
+
+impl CpuBreakdown {
+    /// Each state with the name used in sensor ids, in `/proc/stat` order.
+    pub fn fields(&self) -> [(&'static str, f64); 10] {
+        [
+            ("user", self.user),
+            ("nice", self.nice),
+            ("system", self.system),
+            ("idle", self.idle),
+            ("iowait", self.iowait),
+            ("irq", self.irq),
+            ("softirq", self.softirq),
+            ("steal", self.steal),
+            ("guest", self.guest),
+            ("guest_nice", self.guest_nice),
+        ]
+    }
+}
+
+/// Parses the `cpu` and `cpuN` lines of `/proc/stat`, keyed by that first
+/// word. Older kernels print fewer columns; the missing ones stay zero.
+pub fn parse_proc_stat(text: &str) -> Vec<(String, CpuTimes)> {
+    text.lines()
+        .filter(|line| line.starts_with("cpu"))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?.to_string();
+            let values: Vec<u64> = parts.map(str::parse).collect::<Result<_, _>>().ok()?;
+            let value = |i: usize| values.get(i).copied().unwrap_or(0);
+            let times = CpuTimes {
+                user: value(0),
+                nice: value(1),
+                system: value(2),
+                idle: value(3),
+                iowait: value(4),
+                irq: value(5),
+                softirq: value(6),
+                steal: value(7),
+                guest: value(8),
+                guest_nice: value(9),
+            };
+            Some((name, times))
+        })
+        .collect()
+}
+
+/// Publishes the breakdown for all CPUs together (device `cpu`) and for each
+/// one (device `cpuN`), from the second sample on.
+pub struct ProcStatSource {
+    path: PathBuf,
+    previous: HashMap<String, CpuTimes>,
+}
+
+impl ProcStatSource {
+    pub fn new() -> Self {
+        Self::with_path(DEFAULT_PROC_STAT)
+    }
+
+    /// Reads `path` instead of `/proc/stat`, e.g. a recorded copy.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            previous: HashMap::new(),
+        }
+    }
+}
+
+impl Default for ProcStatSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for ProcStatSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        let Some(text) = read_string(&self.path) else {
+            return;
+        };
+
+        for (name, times) in parse_proc_stat(&text) {
+            let breakdown = self
+                .previous
+                .get(&name)
+                .and_then(|previous| times.breakdown_since(previous));
+            if let Some(breakdown) = breakdown {
+                let cpu = match name.strip_prefix("cpu") {
+                    Some("") => "CPU".to_string(),
+                    Some(index) => format!("CPU {index}"),
+                    None => name.clone(),
+                };
+                for (field, value) in breakdown.fields() {
+                    snapshot.sensors.push(Sensor::new(
+                        format!("procstat/{name}/{field}"),
+                        HardwareGroup::Cpu,
+                        name.as_str(),
+                        format!("{cpu} {field}"),
+                        SensorKind::Load,
+                        value,
+                    ));
+                }
+            }
+            self.previous.insert(name, times);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEFORE: &str = "\
+cpu  10000 500 3000 80000 400 100 200 0 1000 100
+cpu0 5000 250 1500 40000 200 50 100 0 500 50
+cpu1 5000 250 1500 40000 200 50 100 0 500 50
+intr 123456 0 0
+ctxt 987654
+";
+
+    const AFTER: &str = "\
+cpu  10400 600 3100 81200 350 100 200 0 1200 150
+cpu0 5000 250 1500 40000 200 50 100 0 500 50
+cpu1 5400 350 1600 41200 150 50 100 0 700 100
+intr 123999 0 0
+ctxt 999999
+";
+
+    fn times(text: &str, name: &str) -> CpuTimes {
+        parse_proc_stat(text).into_iter().find(|(cpu, _)| cpu == name).unwrap().1
+    }
+
+    #[test]
+    fn parses_cpu_lines_only() {
+        let names: Vec<String> = parse_proc_stat(BEFORE).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["cpu", "cpu0", "cpu1"]);
+        assert_eq!(times(BEFORE, "cpu").guest_nice, 100);
+    }
+
+    #[test]
+    fn older_kernels_leave_missing_columns_at_zero() {
+        let times = times("cpu  100 20 30 400\n", "cpu");
+        assert_eq!(
+            times,
+            CpuTimes {
+                user: 100,
+                nice: 20,
+                system: 30,
+                idle: 400,
+                ..CpuTimes::default()
+            }
+        );
+    }
+
+    #[test]
+    fn breakdown_takes_guests_out_of_user_and_nice() {
+        // 400 user ticks of which 200 guest, 100 nice of which 50 guest_nice,
+        // 100 system, 1200 idle; iowait went backwards and counts as zero.
+        let breakdown = times(AFTER, "cpu").breakdown_since(&times(BEFORE, "cpu")).unwrap();
+        let total = 1800.0;
+        assert_eq!(breakdown.user, 200.0 / total * 100.0);
+        assert_eq!(breakdown.guest, 200.0 / total * 100.0);
+        assert_eq!(breakdown.nice, 50.0 / total * 100.0);
+        assert_eq!(breakdown.guest_nice, 50.0 / total * 100.0);
+        assert_eq!(breakdown.idle, 1200.0 / total * 100.0);
+        assert_eq!(breakdown.iowait, 0.0);
+        let sum: f64 = breakdown.fields().iter().map(|(_, share)| share).sum();
+        assert!((sum - 100.0).abs() < 1e-9, "{sum}");
+    }
+
+    #[test]
+    fn guest_time_never_exceeds_user_time() {
+        let before = CpuTimes::default();
+        let after = CpuTimes {
+            user: 10,
+            idle: 90,
+            guest: 25,
+            ..CpuTimes::default()
+        };
+        let breakdown = after.breakdown_since(&before).unwrap();
+        assert_eq!((breakdown.user, breakdown.guest), (0.0, 10.0));
+    }
+
+    #[test]
+    fn no_ticks_means_no_breakdown() {
+        let cpu0 = times(AFTER, "cpu0");
+        assert_eq!(cpu0.breakdown_since(&times(BEFORE, "cpu0")), None);
+        // A CPU that came back online starts from zero.
+        assert_eq!(CpuTimes::default().breakdown_since(&cpu0), None);
+    }
+}
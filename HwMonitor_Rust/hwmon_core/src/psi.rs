@@ -0,0 +1,254 @@
+//! Pressure stall information from `/proc/pressure/{cpu,memory,io}`.
+//!
+//! Each file has a `some` line (share of time at least one task was stalled
+//! on the resource) and, except for CPU on older kernels, a `full` line (all
+//! non-idle tasks stalled at once):
+//!
+//! ```text
+//! some avg10=0.12 avg60=0.05 avg300=0.01 total=123456
+//! full avg10=0.00 avg60=0.00 avg300=0.00 total=6789
+//! ```
+//!
+//! The averages are percentages over 10 s, 60 s and 300 s; `total` is the
+//! cumulative stall time in microseconds. Cgroup v2 `cpu.pressure`,
+//! `memory.pressure` and `io.pressure` files use the same format.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::sysfs::read_string;
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot, Unit};
+
+pub const DEFAULT_PRESSURE_ROOT: &str = "/proc/pressure";
+
+/// Default `full avg10` percentages for a Warning and a Critical alert.
+pub const DEFAULT_WARNING: f64 = 10.0;
+pub const DEFAULT_CRITICAL: f64 = 40.0;
+
+pub const RESOURCES: [&str; 3] = ["cpu", "memory", "io"];
+
+/// One `some` or `full` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Cumulative stall time in microseconds.
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    pub some: Option<PressureLine>,
+    pub full: Option<PressureLine>,
+}
+
+impl Pressure {
+    /// The lines present, named as in the file.
+    pub fn lines(&self) -> impl Iterator<Item = (&'static str, &PressureLine)> {
+        [("some", self.some.as_ref()), ("full", self.full.as_ref())]
+            .into_iter()
+            .filter_map(|(name, line)| Some((name, line?)))
+    }
+}
+
+/// Parses a pressure file; lines that do not parse are left out.
+pub fn parse_pressure(text: &str) -> Pressure {
+    let mut pressure = Pressure::default();
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("some") => pressure.some = parse_line(parts),
+            Some("full") => pressure.full = parse_line(parts),
+            _ => {}
+        }
+    }
+    pressure
+}
+
+fn parse_line<'a>(parts: impl Iterator<Item = &'a str>) -> Option<PressureLine> {
+    let fields: HashMap<&str, &str> = parts.filter_map(|part| part.split_once('=')).collect();
+    Some(PressureLine {
+        avg10: fields.get("avg10")?.parse().ok()?,
+        avg60: fields.get("avg60")?.parse().ok()?,
+        avg300: fields.get("avg300")?.parse().ok()?,
+        total: fields.get("total")?.parse().ok()?,
+    })
+}
+
+/// Reads and parses one pressure file, system-wide or from a cgroup.
+pub fn read_pressure(path: impl AsRef<Path>) -> Option<Pressure> {
+    read_string(path).map(|text| parse_pressure(&text))
+}
+
+/// Publishes the averages and the share and time of the last interval spent
+/// stalled, per resource and line, and alerts when the `full` line's 10 s
+/// average crosses a limit.
+pub struct PsiSource {
+    root: PathBuf,
+    warning: f64,
+    critical: f64,
+    /// Stall totals from the previous sample, by resource and line.
+    previous: HashMap<(&'static str, &'static str), (u64, Instant)>,
+}
+
+impl PsiSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_PRESSURE_ROOT)
+    }
+
+    /// Reads from `root` instead of `/proc/pressure`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            warning: DEFAULT_WARNING,
+            critical: DEFAULT_CRITICAL,
+            previous: HashMap::new(),
+        }
+    }
+
+    /// Alerts at `warning` and `critical` percent `full avg10` instead of the defaults.
+    pub fn with_limits(mut self, warning: f64, critical: f64) -> Self {
+        self.warning = warning;
+        self.critical = critical;
+        self
+    }
+
+    /// [`collect`](SensorSource::collect) with the stall totals read at `now`.
+    fn collect_at(&mut self, snapshot: &mut Snapshot, now: Instant) {
+        for resource in RESOURCES {
+            let Some(pressure) = read_pressure(self.root.join(resource)) else {
+                continue;
+            };
+            let name = match resource {
+                "cpu" => "CPU",
+                "memory" => "Memory",
+                _ => "I/O",
+            };
+            for (kind, line) in pressure.lines() {
+                let sensor = |field: &str, value: f64| {
+                    Sensor::new(
+                        format!("psi/{resource}/{kind}/{field}"),
+                        HardwareGroup::Pressure,
+                        resource,
+                        format!("{name} {kind} {field}"),
+                        SensorKind::Load,
+                        value,
+                    )
+                };
+                snapshot.sensors.push(sensor("avg10", line.avg10));
+                snapshot.sensors.push(sensor("avg60", line.avg60));
+                snapshot.sensors.push(sensor("avg300", line.avg300));
+
+                if let Some((total, read_at)) = self.previous.insert((resource, kind), (line.total, now)) {
+                    let elapsed = now.duration_since(read_at).as_micros() as f64;
+                    if elapsed > 0.0 {
+                        let stall_time = line.total.saturating_sub(total) as f64;
+                        snapshot.sensors.push(sensor("stalled", (stall_time / elapsed * 100.0).min(100.0)));
+                        snapshot.sensors.push(
+                            Sensor::new(
+                                format!("psi/{resource}/{kind}/stall_time"),
+                                HardwareGroup::Pressure,
+                                resource,
+                                format!("{name} {kind} stall time"),
+                                SensorKind::Latency,
+                                stall_time,
+                            )
+                            .with_unit(Unit::Microseconds),
+                        );
+                    }
+                }
+            }
+
+            if let Some(full) = pressure.full {
+                let severity = if full.avg10 >= self.critical {
+                    Severity::Critical
+                } else if full.avg10 >= self.warning {
+                    Severity::Warning
+                } else {
+                    continue;
+                };
+                snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Pressure,
+                    resource,
+                    severity,
+                    format!("{name} pressure: all tasks stalled {:.1}% of the last 10 s", full.avg10),
+                ));
+            }
+        }
+    }
+}
+
+impl Default for PsiSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for PsiSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        self.collect_at(snapshot, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    #[test]
+    fn parses_some_and_full() {
+        let pressure = parse_pressure(
+            "some avg10=1.50 avg60=0.75 avg300=0.20 total=123456\nfull avg10=0.00 avg60=0.00 avg300=0.00 total=6789\n",
+        );
+        assert_eq!(
+            pressure.some,
+            Some(PressureLine {
+                avg10: 1.5,
+                avg60: 0.75,
+                avg300: 0.2,
+                total: 123456
+            })
+        );
+        assert_eq!(pressure.full.map(|full| full.total), Some(6789));
+    }
+
+    #[test]
+    fn alerts_on_full_pressure_and_reports_stall_time() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |memory_total: u64| {
+            write_tree(
+                root.path(),
+                &[
+                    ("cpu", "some avg10=2.00 avg60=1.00 avg300=0.50 total=1000\n"),
+                    (
+                        "memory",
+                        &format!(
+                            "some avg10=30.00 avg60=5.00 avg300=1.00 total={memory_total}\n\
+                             full avg10=12.50 avg60=3.00 avg300=0.60 total={memory_total}\n"
+                        ),
+                    ),
+                    ("io", "some avg10=0.00 avg60=0.00 avg300=0.00 total=0\nfull avg10=45.00 avg60=0.00 avg300=0.00 total=0\n"),
+                ],
+            );
+        };
+        let mut source = PsiSource::with_root(root.path());
+        write(1_000_000);
+        let start = Instant::now();
+        source.collect_at(&mut Snapshot::default(), start);
+        write(1_004_000);
+        let mut snapshot = Snapshot::default();
+        source.collect_at(&mut snapshot, start + std::time::Duration::from_millis(100));
+
+        let alerts: Vec<(&str, Severity)> =
+            snapshot.alerts.iter().map(|alert| (alert.device.as_str(), alert.severity)).collect();
+        assert_eq!(alerts, [("memory", Severity::Warning), ("io", Severity::Critical)]);
+
+        let stall_time = snapshot.sensor(&"psi/memory/full/stall_time".into()).unwrap();
+        assert_eq!(stall_time.value, 4000.0);
+        assert_eq!(stall_time.display_value(), "4000 µs");
+        assert_eq!(snapshot.sensor(&"psi/memory/full/stalled".into()).unwrap().value, 4.0);
+        assert_eq!(snapshot.sensor(&"psi/cpu/some/stall_time".into()).unwrap().value, 0.0);
+    }
+}
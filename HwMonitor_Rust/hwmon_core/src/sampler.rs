@@ -0,0 +1,71 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{SensorSource, Snapshot};
+
+/// Interval used by the monitor when nothing else is configured.
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Runs a [`SensorSource`] on a dedicated thread at a fixed interval.
+///
+/// Readers only ever see the most recently published snapshot, so a slow
+/// sensor read delays the next sample rather than whoever is displaying it.
+/// The thread is stopped and joined when the sampler is dropped.
+pub struct Sampler {
+    latest: Arc<RwLock<Arc<Snapshot>>>,
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Sampler {
+    /// Starts sampling `source` every `interval`, calling `on_sample` after
+    /// each new snapshot has been published.
+    pub fn spawn<S, F>(mut source: S, interval: Duration, on_sample: F) -> Self
+    where
+        S: SensorSource + Send + 'static,
+        F: Fn() + Send + 'static,
+    {
+        let latest = Arc::new(RwLock::new(Arc::new(Snapshot::default())));
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+
+        let published = Arc::clone(&latest);
+        let handle = thread::Builder::new()
+            .name("hwmon-sampler".into())
+            .spawn(move || loop {
+                let started = Instant::now();
+                let snapshot = Arc::new(source.snapshot());
+                *published.write().unwrap_or_else(|e| e.into_inner()) = snapshot;
+                on_sample();
+
+                // Waiting on the stop channel doubles as the sleep, so dropping
+                // the sampler does not have to wait out a full interval.
+                match stop_rx.recv_timeout(interval.saturating_sub(started.elapsed())) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            })
+            .expect("failed to spawn sampler thread");
+
+        Self {
+            latest,
+            stop: Some(stop_tx),
+            handle: Some(handle),
+        }
+    }
+
+    /// The most recent snapshot; empty until the first sample completes.
+    pub fn latest(&self) -> Arc<Snapshot> {
+        Arc::clone(&self.latest.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::fmt;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+/// Stable identifier for a sensor, e.g. `hwmon/k10temp/temp1`.
+///
+/// Ids are built from where the reading comes from rather than from labels,
+/// so they survive relabelling and can be pinned in config.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(transparent)]
+pub struct SensorId(String);
+
+impl SensorId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SensorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for SensorId {
+    fn from(id: &str) -> Self {
+        Self::new(id)
+    }
+}
+
+impl From<String> for SensorId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+/// The piece of hardware a sensor belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HardwareGroup {
+    Cpu,
+    Gpu,
+    Memory,
+    Storage,
+    Network,
+    Board,
+    /// Voltage, current and power rails from the motherboard and PSU.
+    Power,
+    Battery,
+    /// Pressure stall information: how much time tasks spend waiting.
+    Pressure,
+    /// systemd units and containers.
+    Cgroups,
+}
+
+impl HardwareGroup {
+    /// All groups in display order.
+    pub const ALL: [HardwareGroup; 10] = [
+        HardwareGroup::Cpu,
+        HardwareGroup::Gpu,
+        HardwareGroup::Memory,
+        HardwareGroup::Storage,
+        HardwareGroup::Network,
+        HardwareGroup::Board,
+        HardwareGroup::Power,
+        HardwareGroup::Battery,
+        HardwareGroup::Pressure,
+        HardwareGroup::Cgroups,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HardwareGroup::Cpu => "CPU",
+            HardwareGroup::Gpu => "GPU",
+            HardwareGroup::Memory => "Memory",
+            HardwareGroup::Storage => "Storage",
+            HardwareGroup::Network => "Network",
+            HardwareGroup::Board => "Board",
+            HardwareGroup::Power => "Board/Power",
+            HardwareGroup::Battery => "Battery",
+            HardwareGroup::Pressure => "Pressure",
+            HardwareGroup::Cgroups => "Cgroups",
+        }
+    }
+}
+
+/// What physical quantity a sensor measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorKind {
+    Temperature,
+    Load,
+    Fan,
+    Voltage,
+    Current,
+    Power,
+    Clock,
+    Throughput,
+    /// Events per second, such as packets or I/O operations.
+    Rate,
+    /// How long requests take to complete.
+    Latency,
+    /// An amount of storage or memory, such as VRAM in use.
+    Data,
+    /// A plain number of events, such as corrected memory errors.
+    Count,
+    /// Energy used so far, e.g. by the CPU package since start-up.
+    Energy,
+}
+
+impl SensorKind {
+    /// The unit readings of this kind are reported in unless a source says otherwise.
+    pub fn default_unit(self) -> Unit {
+        match self {
+            SensorKind::Temperature => Unit::Celsius,
+            SensorKind::Load => Unit::Percent,
+            SensorKind::Fan => Unit::Rpm,
+            SensorKind::Voltage => Unit::Volts,
+            SensorKind::Current => Unit::Amps,
+            SensorKind::Power => Unit::Watts,
+            SensorKind::Clock => Unit::Megahertz,
+            SensorKind::Throughput => Unit::BytesPerSecond,
+            SensorKind::Rate => Unit::PerSecond,
+            SensorKind::Latency => Unit::Milliseconds,
+            SensorKind::Data => Unit::Bytes,
+            SensorKind::Count => Unit::Count,
+            SensorKind::Energy => Unit::WattHours,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Celsius,
+    Percent,
+    Rpm,
+    Volts,
+    Amps,
+    Watts,
+    Megahertz,
+    Bytes,
+    BytesPerSecond,
+    PerSecond,
+    Milliseconds,
+    Microseconds,
+    Count,
+    WattHours,
+}
+
+impl Unit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Celsius => "°C",
+            Unit::Percent => "%",
+            Unit::Rpm => "RPM",
+            Unit::Volts => "V",
+            Unit::Amps => "A",
+            Unit::Watts => "W",
+            Unit::Megahertz => "MHz",
+            Unit::Bytes => "B",
+            Unit::BytesPerSecond => "B/s",
+            Unit::PerSecond => "/s",
+            Unit::Milliseconds => "ms",
+            Unit::Microseconds => "µs",
+            Unit::Count => "",
+            Unit::WattHours => "Wh",
+        }
+    }
+
+    /// Number of decimals worth showing for a value in this unit.
+    fn precision(self) -> usize {
+        match self {
+            Unit::Volts | Unit::WattHours => 3,
+            Unit::Amps | Unit::Milliseconds => 2,
+            Unit::Celsius | Unit::Percent | Unit::Watts | Unit::PerSecond => 1,
+            Unit::Rpm | Unit::Megahertz | Unit::Microseconds | Unit::Count | Unit::Bytes | Unit::BytesPerSecond => 0,
+        }
+    }
+
+    /// `value` with this unit, rounded for display.
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Unit::Bytes => return format_bytes(value),
+            Unit::BytesPerSecond => return format!("{}/s", format_bytes(value)),
+            _ => {}
+        }
+
+        let symbol = self.symbol();
+        let separator = if symbol.starts_with(char::is_alphabetic) { " " } else { "" };
+        format!("{:.*}{separator}{symbol}", self.precision(), value)
+    }
+}
+
+/// One reading of one sensor.
+#[derive(Debug, Clone)]
+pub struct Sensor {
+    pub id: SensorId,
+    pub group: HardwareGroup,
+    /// The chip or device the reading came from, e.g. `k10temp` or `card0`.
+    pub device: String,
+    pub label: String,
+    pub kind: SensorKind,
+    pub unit: Unit,
+    pub value: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub critical: Option<f64>,
+    pub timestamp: SystemTime,
+}
+
+impl Sensor {
+    /// Creates a reading taken now, in the kind's default unit and without limits.
+    pub fn new(
+        id: impl Into<SensorId>,
+        group: HardwareGroup,
+        device: impl Into<String>,
+        label: impl Into<String>,
+        kind: SensorKind,
+        value: f64,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            group,
+            device: device.into(),
+            label: label.into(),
+            kind,
+            unit: kind.default_unit(),
+            value,
+            min: None,
+            max: None,
+            critical: None,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn with_min(mut self, min: Option<f64>) -> Self {
+        self.min = min;
+        self
+    }
+
+    pub fn with_max(mut self, max: Option<f64>) -> Self {
+        self.max = max;
+        self
+    }
+
+    pub fn with_critical(mut self, critical: Option<f64>) -> Self {
+        self.critical = critical;
+        self
+    }
+
+    /// The value with its unit, rounded for display.
+    pub fn display_value(&self) -> String {
+        self.unit.format(self.value)
+    }
+
+    /// Whether the value is below `min` or above `max`.
+    pub fn out_of_range(&self) -> bool {
+        self.min.is_some_and(|min| self.value < min) || self.max.is_some_and(|max| self.value > max)
+    }
+}
+
+/// Formats a byte count with binary prefixes, e.g. `3.2 GiB`.
+pub fn format_bytes(bytes: f64) -> String {
+    const PREFIXES: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes;
+    let mut prefix = 0;
+    while value.abs() >= 1024.0 && prefix < PREFIXES.len() - 1 {
+        value /= 1024.0;
+        prefix += 1;
+    }
+
+    if prefix == 0 {
+        format!("{value:.0} {}", PREFIXES[prefix])
+    } else {
+        format!("{value:.1} {}", PREFIXES[prefix])
+    }
+}
@@ -0,0 +1,451 @@
+//! SMART health through `smartctl --json -n standby -a`.
+//!
+//! smartctl is slow, so each drive is queried at most once per
+//! [`DEFAULT_SMART_INTERVAL`], on a thread of its own, and the result is
+//! reused in between. `-n standby` keeps it from spinning up a sleeping disk;
+//! the last result is kept until the disk is awake again.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_SMARTCTL: &str = "smartctl";
+pub const DEFAULT_SMART_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How long a query may run before the drive gets an alert about it; a
+/// failing disk can keep smartctl waiting on I/O for minutes.
+const SLOW_QUERY: Duration = Duration::from_secs(60);
+
+/// The parts of a SMART report worth showing. ATA and NVMe drives each fill
+/// in their own subset.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SmartHealth {
+    /// Overall self-assessment; `false` means the drive predicts its own failure.
+    pub passed: Option<bool>,
+    pub temperature: Option<f64>,
+    pub power_on_hours: Option<u64>,
+    /// ATA attribute 5.
+    pub reallocated_sectors: Option<u64>,
+    /// ATA attribute 197.
+    pub pending_sectors: Option<u64>,
+    /// NVMe endurance estimate; can exceed 100.
+    pub percentage_used: Option<u64>,
+    pub media_errors: Option<u64>,
+}
+
+impl SmartHealth {
+    /// The counters as Storage sensors of `drive`.
+    pub fn sensors(&self, drive: &str) -> Vec<Sensor> {
+        let sensor = |name: &str, label: &str, kind: SensorKind, value: u64| {
+            Sensor::new(format!("smart/{drive}/{name}"), HardwareGroup::Storage, drive, label, kind, value as f64)
+        };
+        let mut sensors = Vec::new();
+        if let Some(hours) = self.power_on_hours {
+            sensors.push(sensor("power_on_hours", "Power-On Hours", SensorKind::Count, hours));
+        }
+        if let Some(count) = self.reallocated_sectors {
+            sensors.push(sensor("reallocated_sectors", "Reallocated Sectors", SensorKind::Count, count));
+        }
+        if let Some(count) = self.pending_sectors {
+            sensors.push(sensor("pending_sectors", "Pending Sectors", SensorKind::Count, count));
+        }
+        if let Some(used) = self.percentage_used {
+            sensors.push(sensor("percentage_used", "Endurance Used", SensorKind::Load, used));
+        }
+        if let Some(count) = self.media_errors {
+            sensors.push(sensor("media_errors", "Media Errors", SensorKind::Count, count));
+        }
+        sensors
+    }
+
+    /// Alerts for a failing or degrading drive.
+    pub fn alerts(&self, drive: &str) -> Vec<Alert> {
+        let alert = |severity, message: String| Alert::new(HardwareGroup::Storage, drive, severity, message);
+        let mut alerts = Vec::new();
+
+        if self.passed == Some(false) {
+            alerts.push(alert(Severity::Critical, "SMART overall health check FAILED".to_string()));
+        }
+        if let Some(count) = self.reallocated_sectors.filter(|count| *count > 0) {
+            alerts.push(alert(Severity::Warning, format!("{count} reallocated sectors")));
+        }
+        if let Some(count) = self.pending_sectors.filter(|count| *count > 0) {
+            alerts.push(alert(Severity::Warning, format!("{count} sectors pending reallocation")));
+        }
+        if let Some(count) = self.media_errors.filter(|count| *count > 0) {
+            alerts.push(alert(Severity::Warning, format!("{count} media errors")));
+        }
+        match self.percentage_used {
+            Some(used) if used >= 100 => alerts.push(alert(Severity::Critical, format!("{used}% of rated endurance used"))),
+            Some(used) if used >= 90 => alerts.push(alert(Severity::Warning, format!("{used}% of rated endurance used"))),
+            _ => {}
+        }
+        alerts
+    }
+}
+
+/// Parses the JSON printed by `smartctl --json -n standby -a`; `Ok(None)`
+/// when the drive was asleep and smartctl left it alone.
+///
+/// smartctl reports problems opening the device inside the JSON itself, so
+/// those come back as errors carrying its message.
+pub fn parse_smartctl_json(text: &str) -> Result<Option<SmartHealth>, String> {
+    let json: Value = serde_json::from_str(text).map_err(|e| format!("invalid smartctl output: {e}"))?;
+
+    // Bits 0 and 1 of the exit status mean the command line or the device
+    // open failed; the others describe the drive. Skipping a sleeping drive
+    // also sets bit 1, with "Device is in STANDBY mode, exit(2)".
+    let exit_status = json["smartctl"]["exit_status"].as_u64().unwrap_or(0);
+    if exit_status & 0b11 != 0 {
+        let message = json["smartctl"]["messages"][0]["string"]
+            .as_str()
+            .unwrap_or("smartctl could not read the device");
+        if message.starts_with("Device is in ") {
+            return Ok(None);
+        }
+        return Err(message.to_string());
+    }
+
+    let attribute = |id: u64| {
+        json["ata_smart_attributes"]["table"]
+            .as_array()?
+            .iter()
+            .find(|attribute| attribute["id"].as_u64() == Some(id))?["raw"]["value"]
+            .as_u64()
+    };
+    let nvme = &json["nvme_smart_health_information_log"];
+
+    Ok(Some(SmartHealth {
+        passed: json["smart_status"]["passed"].as_bool(),
+        temperature: json["temperature"]["current"].as_f64(),
+        power_on_hours: json["power_on_time"]["hours"].as_u64(),
+        reallocated_sectors: attribute(5),
+        pending_sectors: attribute(197),
+        percentage_used: nvme["percentage_used"].as_u64(),
+        media_errors: nvme["media_errors"].as_u64(),
+    }))
+}
+
+/// Runs smartctl on `/dev/{drive}`; `Err` only when it could not be started.
+fn run_smartctl(command: &Path, drive: &str) -> io::Result<Result<Option<SmartHealth>, String>> {
+    let output = Command::new(command)
+        .args(["--json", "-n", "standby", "-a"])
+        .arg(format!("/dev/{drive}"))
+        .output()?;
+    Ok(parse_smartctl_json(&String::from_utf8_lossy(&output.stdout)))
+}
+
+type QueryResult = io::Result<Result<Option<SmartHealth>, String>>;
+
+#[derive(Default)]
+struct DriveState {
+    /// When the last query finished.
+    queried: Option<Instant>,
+    /// The last answer from an awake drive.
+    result: Option<Result<SmartHealth, String>>,
+    /// A query still running, with when it started.
+    pending: Option<(Instant, Receiver<QueryResult>)>,
+}
+
+/// Adds SMART data to the drives found by [`StorageSource`](crate::StorageSource),
+/// so it must run after it.
+pub struct SmartSource {
+    command: PathBuf,
+    interval: Duration,
+    /// Cleared once the command turns out not to exist.
+    available: bool,
+    drives: HashMap<String, DriveState>,
+}
+
+impl SmartSource {
+    pub fn new() -> Self {
+        Self::with_command(DEFAULT_SMARTCTL)
+    }
+
+    /// Runs `command` instead of `smartctl`, e.g. a stub printing recorded JSON.
+    pub fn with_command(command: impl Into<PathBuf>) -> Self {
+        Self {
+            command: command.into(),
+            interval: DEFAULT_SMART_INTERVAL,
+            available: true,
+            drives: HashMap::new(),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Queries `drive` on the calling thread; `Ok(None)` if it is asleep.
+    pub fn query(&self, drive: &str) -> io::Result<Result<Option<SmartHealth>, String>> {
+        run_smartctl(&self.command, drive)
+    }
+
+    /// Picks up a finished query and starts a new one when the last is
+    /// older than the interval. Returns the last result and, while a query
+    /// is running, when it started.
+    fn health(&mut self, drive: &str) -> (Option<&Result<SmartHealth, String>>, Option<Instant>) {
+        let state = self.drives.entry(drive.to_string()).or_default();
+
+        if let Some((_, receiver)) = &state.pending {
+            let finished = match receiver.try_recv() {
+                Ok(Ok(Ok(Some(health)))) => Some(Some(Ok(health))),
+                // Asleep: keep what we had.
+                Ok(Ok(Ok(None))) => Some(None),
+                Ok(Ok(Err(message))) => Some(Some(Err(message))),
+                Ok(Err(e)) if e.kind() == io::ErrorKind::NotFound => {
+                    self.available = false;
+                    Some(None)
+                }
+                Ok(Err(e)) => Some(Some(Err(e.to_string()))),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(None),
+            };
+            if let Some(result) = finished {
+                state.pending = None;
+                state.queried = Some(Instant::now());
+                if result.is_some() {
+                    state.result = result;
+                }
+            }
+        }
+
+        let stale = match state.queried {
+            Some(queried) => queried.elapsed() >= self.interval,
+            None => true,
+        };
+        if stale && state.pending.is_none() && self.available {
+            let (sender, receiver) = mpsc::channel();
+            let (command, name) = (self.command.clone(), drive.to_string());
+            thread::spawn(move || {
+                let _ = sender.send(run_smartctl(&command, &name));
+            });
+            state.pending = Some((Instant::now(), receiver));
+        }
+
+        (state.result.as_ref(), state.pending.as_ref().map(|(started, _)| *started))
+    }
+}
+
+impl Default for SmartSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for SmartSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        let mut drives = std::mem::take(&mut snapshot.drives);
+
+        for drive in &mut drives {
+            let (result, started) = self.health(&drive.name);
+            if let Some(started) = started.filter(|started| started.elapsed() >= SLOW_QUERY) {
+                snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Storage,
+                    drive.name.as_str(),
+                    Severity::Warning,
+                    format!("smartctl has not answered for {} s", started.elapsed().as_secs()),
+                ));
+            }
+            match result {
+                Some(Ok(health)) => {
+                    snapshot.alerts.extend(health.alerts(&drive.name));
+                    snapshot.sensors.extend(health.sensors(&drive.name));
+
+                    // SATA drives only have a hwmon sensor when drivetemp is loaded.
+                    let has_temperature = snapshot.group(HardwareGroup::Storage).any(|sensor| {
+                        sensor.device == drive.name && sensor.kind == SensorKind::Temperature
+                    });
+                    if let (Some(temperature), false) = (health.temperature, has_temperature) {
+                        snapshot.sensors.push(Sensor::new(
+                            format!("smart/{}/temperature", drive.name),
+                            HardwareGroup::Storage,
+                            drive.name.as_str(),
+                            "Drive Temperature (SMART)",
+                            SensorKind::Temperature,
+                            temperature,
+                        ));
+                    }
+                    drive.smart = Some(health.clone());
+                }
+                Some(Err(message)) => snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Storage,
+                    drive.name.as_str(),
+                    Severity::Info,
+                    format!("SMART unavailable: {message}"),
+                )),
+                None => {}
+            }
+        }
+
+        snapshot.drives = drives;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    const ATA: &str = r#"{
+      "smartctl": {"version": [7, 4], "exit_status": 0},
+      "device": {"name": "/dev/sda", "type": "sat"},
+      "smart_status": {"passed": true},
+      "temperature": {"current": 34},
+      "power_on_time": {"hours": 23817},
+      "ata_smart_attributes": {"table": [
+        {"id": 5, "name": "Reallocated_Sector_Ct", "value": 100, "raw": {"value": 8, "string": "8"}},
+        {"id": 9, "name": "Power_On_Hours", "value": 73, "raw": {"value": 23817, "string": "23817"}},
+        {"id": 197, "name": "Current_Pending_Sector", "value": 100, "raw": {"value": 0, "string": "0"}}
+      ]}
+    }"#;
+
+    const NVME: &str = r#"{
+      "smartctl": {"version": [7, 4], "exit_status": 0},
+      "device": {"name": "/dev/nvme0", "type": "nvme"},
+      "smart_status": {"passed": true, "nvme": {"value": 0}},
+      "temperature": {"current": 41},
+      "power_on_time": {"hours": 5120},
+      "nvme_smart_health_information_log": {"critical_warning": 0, "temperature": 41,
+        "percentage_used": 3, "power_on_hours": 5120, "media_errors": 0}
+    }"#;
+
+    const STANDBY: &str = r#"{
+      "smartctl": {"version": [7, 4], "exit_status": 2,
+        "messages": [{"string": "Device is in STANDBY mode, exit(2)", "severity": "information"}]},
+      "device": {"name": "/dev/sdb", "type": "sat"}
+    }"#;
+
+    const OPEN_FAILED: &str = r#"{
+      "smartctl": {"version": [7, 4], "exit_status": 2,
+        "messages": [{"string": "Smartctl open device: /dev/sdc failed: Permission denied", "severity": "error"}]}
+    }"#;
+
+    #[test]
+    fn parses_ata_attributes() {
+        let health = parse_smartctl_json(ATA).unwrap().unwrap();
+        assert_eq!(
+            health,
+            SmartHealth {
+                passed: Some(true),
+                temperature: Some(34.0),
+                power_on_hours: Some(23817),
+                reallocated_sectors: Some(8),
+                pending_sectors: Some(0),
+                percentage_used: None,
+                media_errors: None,
+            }
+        );
+        let alerts: Vec<String> = health.alerts("sda").into_iter().map(|alert| alert.message).collect();
+        assert_eq!(alerts, ["8 reallocated sectors"]);
+    }
+
+    #[test]
+    fn parses_nvme_health_log() {
+        let health = parse_smartctl_json(NVME).unwrap().unwrap();
+        assert_eq!(health.percentage_used, Some(3));
+        assert_eq!(health.media_errors, Some(0));
+        assert_eq!(health.reallocated_sectors, None);
+
+        let ids: Vec<String> = health.sensors("nvme0n1").iter().map(|sensor| sensor.id.as_str().to_string()).collect();
+        assert_eq!(
+            ids,
+            [
+                "smart/nvme0n1/power_on_hours",
+                "smart/nvme0n1/percentage_used",
+                "smart/nvme0n1/media_errors"
+            ]
+        );
+    }
+
+    #[test]
+    fn sleeping_drive_is_not_an_error() {
+        assert_eq!(parse_smartctl_json(STANDBY), Ok(None));
+        assert_eq!(
+            parse_smartctl_json(OPEN_FAILED),
+            Err("Smartctl open device: /dev/sdc failed: Permission denied".to_string())
+        );
+    }
+
+    /// A smartctl stand-in that prints whatever `output` holds at the time.
+    #[cfg(unix)]
+    fn stub(dir: &Path, output: &Path) -> PathBuf {
+        let path = dir.join("smartctl");
+        std::fs::write(&path, format!("#!/bin/sh\ncat '{}'\n", output.display())).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    fn drive(name: &str) -> crate::Drive {
+        crate::Drive {
+            name: name.to_string(),
+            model: None,
+            kind: crate::DriveKind::Hdd,
+            size: 0,
+            removable: false,
+            block_devices: Vec::new(),
+            smart: None,
+        }
+    }
+
+    /// Collects until `done` holds, as the sampler would every second.
+    #[cfg(unix)]
+    fn collect_until(source: &mut SmartSource, done: impl Fn(&Snapshot) -> bool) -> Snapshot {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let mut snapshot = Snapshot {
+                drives: vec![drive("sda")],
+                ..Snapshot::default()
+            };
+            source.collect(&mut snapshot);
+            if done(&snapshot) || Instant::now() > deadline {
+                return snapshot;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn publishes_counters_and_keeps_them_while_the_drive_sleeps() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output.json");
+        std::fs::write(&output, ATA).unwrap();
+        let mut source = SmartSource::with_command(stub(dir.path(), &output)).with_interval(Duration::ZERO);
+
+        let has_counters = |snapshot: &Snapshot| snapshot.sensor(&"smart/sda/reallocated_sectors".into()).is_some();
+        let snapshot = collect_until(&mut source, has_counters);
+        assert_eq!(snapshot.sensor(&"smart/sda/power_on_hours".into()).unwrap().value, 23817.0);
+        assert_eq!(snapshot.drives[0].smart.as_ref().unwrap().passed, Some(true));
+
+        // Once a standby answer has come back, the earlier reading is still there.
+        std::fs::write(&output, STANDBY).unwrap();
+        let written = Instant::now();
+        let pending_since = |source: &SmartSource| source.drives["sda"].pending.as_ref().map(|(started, _)| *started);
+        let standby_query = loop {
+            assert!(written.elapsed() < Duration::from_secs(10), "no query after the drive fell asleep");
+            collect_until(&mut source, |_| true);
+            if let Some(started) = pending_since(&source).filter(|started| *started >= written) {
+                break started;
+            }
+        };
+        while source.drives["sda"].queried.filter(|queried| *queried >= standby_query).is_none() {
+            assert!(written.elapsed() < Duration::from_secs(10), "standby query did not finish");
+            collect_until(&mut source, |_| true);
+        }
+        let snapshot = collect_until(&mut source, |_| true);
+        assert!(has_counters(&snapshot));
+        assert!(snapshot.alerts.iter().all(|alert| !alert.message.starts_with("SMART unavailable")));
+    }
+}
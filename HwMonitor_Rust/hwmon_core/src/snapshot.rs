@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use crate::{Alert, Battery, Cgroup, CpuTemperature, Drive, LogicalCpu, NetInterface, HardwareGroup, Sensor, SensorId};
+
+/// A single point-in-time view of everything the sources collected.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub sensors: Vec<Sensor>,
+    /// Mounted filesystems.
+    pub disks: Vec<DiskInfo>,
+    /// Physical drives, which mounts can be matched to with [`Drive::holds`].
+    pub drives: Vec<Drive>,
+    /// Network interfaces that passed the configured filter.
+    pub interfaces: Vec<NetInterface>,
+    pub batteries: Vec<Battery>,
+    /// Whether an AC adapter is plugged in; `None` on machines without one.
+    pub ac_online: Option<bool>,
+    pub processes: Vec<ProcessInfo>,
+    pub cgroups: Vec<Cgroup>,
+    pub cpu_temperature: Option<CpuTemperature>,
+    /// Logical CPUs with their topology, from [`CpuSource`](crate::CpuSource).
+    pub cpus: Vec<LogicalCpu>,
+    pub alerts: Vec<Alert>,
+}
+
+impl Snapshot {
+    pub fn sensor(&self, id: &SensorId) -> Option<&Sensor> {
+        self.sensors.iter().find(|sensor| &sensor.id == id)
+    }
+
+    /// The reading picked by [`CpuTempSelector`](crate::CpuTempSelector), if any.
+    pub fn cpu_temperature(&self) -> Option<&Sensor> {
+        self.sensor(&self.cpu_temperature.as_ref()?.sensor)
+    }
+
+    /// Alerts raised for one device of `group`.
+    pub fn alerts_for<'a>(&'a self, group: HardwareGroup, device: &'a str) -> impl Iterator<Item = &'a Alert> {
+        self.alerts.iter().filter(move |alert| alert.group == group && alert.device == device)
+    }
+
+    /// Sensors belonging to `group`, in the order sources reported them.
+    pub fn group(&self, group: HardwareGroup) -> impl Iterator<Item = &Sensor> {
+        self.sensors.iter().filter(move |sensor| sensor.group == group)
+    }
+}
+
+/// A mounted disk as reported by the OS.
+#[derive(Debug, Clone)]
+pub struct DiskInfo {
+    pub name: String,
+    pub mount_point: PathBuf,
+    pub kind: String,
+    /// Filesystem size in bytes.
+    pub total_space: u64,
+    /// Bytes an unprivileged user can still write.
+    pub available_space: u64,
+}
+
+/// A running process, with rates over the last sample interval.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub user: Option<String>,
+    /// Percent of one CPU, so a busy multi-threaded process can exceed 100.
+    pub cpu_usage: f64,
+    /// Resident memory in bytes.
+    pub memory: u64,
+    pub disk_read: f64,
+    pub disk_write: f64,
+    /// Only known on Linux.
+    pub threads: Option<usize>,
+}
+
+impl DiskInfo {
+    pub fn used_space(&self) -> u64 {
+        self.total_space.saturating_sub(self.available_space)
+    }
+}
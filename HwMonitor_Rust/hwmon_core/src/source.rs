@@ -0,0 +1,42 @@
+use crate::Snapshot;
+
+/// Something that can read hardware sensors.
+///
+/// Implementations refresh their own backing state on every call, so callers
+/// decide how often sampling happens.
+pub trait SensorSource {
+    /// Refreshes the readings and writes them into `snapshot`.
+    fn collect(&mut self, snapshot: &mut Snapshot);
+
+    /// Convenience wrapper that collects into a fresh snapshot.
+    fn snapshot(&mut self) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        self.collect(&mut snapshot);
+        snapshot
+    }
+}
+
+/// Runs several sources in order against the same snapshot.
+#[derive(Default)]
+pub struct Collector {
+    sources: Vec<Box<dyn SensorSource + Send>>,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, source: impl SensorSource + Send + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+}
+
+impl SensorSource for Collector {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        for source in &mut self.sources {
+            source.collect(snapshot);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Physical drives and their temperatures.
+//!
+//! Drives come from `/sys/block`; anything without a `device` link (loop,
+//! zram, device-mapper) is virtual and skipped. Temperatures come from the
+//! `nvme` and `drivetemp` hwmon drivers, whose `device` link resolves to the
+//! same NVMe controller or SCSI device as the block device's own link. With
+//! native NVMe multipath the block device links to an `nvme-subsysN` instead,
+//! and the controllers are listed inside it.
+//! I/O rates come from [`/proc/diskstats`](crate::diskstats), keyed by the
+//! same kernel names.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::diskstats::{parse_diskstats, DiskIo, DiskStats, DEFAULT_DISKSTATS};
+use crate::hwmon::{read_chips, ChannelType, DEFAULT_HWMON_ROOT};
+use crate::sysfs::{read_string, read_value};
+use crate::{DiskInfo, HardwareGroup, Sensor, SensorKind, SensorSource, SmartHealth, Snapshot};
+
+pub const DEFAULT_BLOCK_ROOT: &str = "/sys/block";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriveKind {
+    Nvme,
+    Ssd,
+    Hdd,
+}
+
+impl DriveKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            DriveKind::Nvme => "NVMe",
+            DriveKind::Ssd => "SSD",
+            DriveKind::Hdd => "HDD",
+        }
+    }
+}
+
+/// A physical drive; its sensors use the drive name as their `device`.
+#[derive(Debug, Clone)]
+pub struct Drive {
+    /// Kernel name, e.g. `nvme0n1` or `sda`.
+    pub name: String,
+    pub model: Option<String>,
+    pub kind: DriveKind,
+    pub size: u64,
+    pub removable: bool,
+    /// Partitions and device-mapper devices stacked on the drive, by kernel
+    /// name (`nvme0n1p2`, `dm-0`) and mapper path (`mapper/cryptroot`).
+    pub block_devices: Vec<String>,
+    /// Filled in by [`SmartSource`](crate::SmartSource) when smartctl can read the drive.
+    pub smart: Option<SmartHealth>,
+}
+
+impl Drive {
+    /// Whether the mounted filesystem `disk` lives on this drive.
+    pub fn holds(&self, disk: &DiskInfo) -> bool {
+        let name = disk.name.strip_prefix("/dev/").unwrap_or(&disk.name);
+        name == self.name || self.block_devices.iter().any(|device| device == name)
+    }
+}
+
+pub struct StorageSource {
+    block_root: PathBuf,
+    hwmon_root: PathBuf,
+    diskstats: PathBuf,
+    previous_stats: Option<(HashMap<String, DiskStats>, Instant)>,
+}
+
+impl StorageSource {
+    pub fn new() -> Self {
+        Self {
+            block_root: DEFAULT_BLOCK_ROOT.into(),
+            hwmon_root: DEFAULT_HWMON_ROOT.into(),
+            diskstats: DEFAULT_DISKSTATS.into(),
+            previous_stats: None,
+        }
+    }
+
+    pub fn with_block_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.block_root = root.into();
+        self
+    }
+
+    pub fn with_hwmon_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.hwmon_root = root.into();
+        self
+    }
+
+    pub fn with_diskstats(mut self, path: impl Into<PathBuf>) -> Self {
+        self.diskstats = path.into();
+        self
+    }
+
+    /// I/O rates since the previous call, by drive name.
+    fn io_rates(&mut self) -> HashMap<String, DiskIo> {
+        let Some(text) = read_string(&self.diskstats) else {
+            return HashMap::new();
+        };
+        let now = Instant::now();
+        let stats: HashMap<String, DiskStats> = parse_diskstats(&text).into_iter().collect();
+
+        let mut rates = HashMap::new();
+        if let Some((previous, read_at)) = &self.previous_stats {
+            for (name, current) in &stats {
+                let io = previous
+                    .get(name)
+                    .and_then(|previous| DiskIo::between(previous, current, now.duration_since(*read_at)));
+                if let Some(io) = io {
+                    rates.insert(name.clone(), io);
+                }
+            }
+        }
+        self.previous_stats = Some((stats, now));
+        rates
+    }
+
+    pub fn drives(&self) -> Vec<Drive> {
+        let Ok(entries) = fs::read_dir(&self.block_root) else {
+            return Vec::new();
+        };
+
+        let mut drives: Vec<Drive> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| read_drive(&self.block_root, &entry.path()))
+            .collect();
+        drives.sort_by(|a, b| a.name.cmp(&b.name));
+        drives
+    }
+}
+
+impl Default for StorageSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for StorageSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        let drives = self.drives();
+
+        let drive_by_device: HashMap<PathBuf, &str> = drives
+            .iter()
+            .flat_map(|drive| {
+                drive_devices(&self.block_root.join(&drive.name).join("device"))
+                    .into_iter()
+                    .map(|device| (device, drive.name.as_str()))
+            })
+            .collect();
+
+        for chip in read_chips(&self.hwmon_root) {
+            if chip.group() != HardwareGroup::Storage {
+                continue;
+            }
+            // Readings of a chip that matches no drive are still published,
+            // under its own device name.
+            let device = match chip.device.as_ref().and_then(|device| drive_by_device.get(device)) {
+                Some(drive) => drive.to_string(),
+                None => chip.device_name().unwrap_or_else(|| chip.name.clone()),
+            };
+            for channel in chip.channels.iter().filter(|c| c.channel_type == ChannelType::Temp) {
+                let mut sensor = chip.sensor(channel);
+                sensor.device = device.clone();
+                // drivetemp has a single unlabelled channel.
+                if channel.label.is_none() {
+                    sensor.label = "Drive Temperature".to_string();
+                }
+                snapshot.sensors.push(sensor);
+            }
+        }
+
+        let rates = self.io_rates();
+        for drive in &drives {
+            let Some(io) = rates.get(&drive.name) else {
+                continue;
+            };
+            let sensor = |name: &str, label: &str, kind: SensorKind, value: f64| {
+                Sensor::new(format!("diskstats/{}/{name}", drive.name), HardwareGroup::Storage, drive.name.as_str(), label, kind, value)
+            };
+            snapshot.sensors.push(sensor("read_bytes", "Read", SensorKind::Throughput, io.read_bytes_per_sec));
+            snapshot.sensors.push(sensor("write_bytes", "Write", SensorKind::Throughput, io.write_bytes_per_sec));
+            snapshot.sensors.push(sensor("read_iops", "Read IOPS", SensorKind::Rate, io.read_iops));
+            snapshot.sensors.push(sensor("write_iops", "Write IOPS", SensorKind::Rate, io.write_iops));
+            if let Some(latency) = io.latency_ms {
+                snapshot.sensors.push(sensor("latency", "Average Latency", SensorKind::Latency, latency));
+            }
+            snapshot.sensors.push(sensor("utilization", "Utilization", SensorKind::Load, io.utilization));
+        }
+
+        snapshot.drives = drives;
+    }
+}
+
+fn read_drive(block_root: &Path, path: &Path) -> Option<Drive> {
+    if !path.join("device").exists() {
+        return None;
+    }
+    let name = path.file_name()?.to_str()?.to_string();
+
+    let kind = if name.starts_with("nvme") {
+        DriveKind::Nvme
+    } else if read_value::<u8>(path.join("queue/rotational")) == Some(1) {
+        DriveKind::Hdd
+    } else {
+        DriveKind::Ssd
+    };
+
+    let mut block_devices = Vec::new();
+    let partitions = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join("partition").exists());
+    for partition in partitions {
+        block_devices.push(partition.file_name().to_string_lossy().into_owned());
+        block_devices.extend(holders(block_root, &partition.path()));
+    }
+    block_devices.extend(holders(block_root, path));
+
+    Some(Drive {
+        model: read_string(path.join("device/model")).filter(|model| !model.is_empty()),
+        kind,
+        // `size` is always in 512-byte sectors, whatever the logical block size.
+        size: read_value::<u64>(path.join("size")).unwrap_or(0) * 512,
+        removable: read_value::<u8>(path.join("removable")) == Some(1),
+        block_devices,
+        smart: None,
+        name,
+    })
+}
+
+/// The devices a drive's hwmon chip can hang off: what the block device's
+/// `device` link resolves to, and for an `nvme-subsysN` the controllers in it.
+fn drive_devices(link: &Path) -> Vec<PathBuf> {
+    let Ok(device) = fs::canonicalize(link) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<PathBuf> = fs::read_dir(&device)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_str().is_some_and(|name| name.starts_with("nvme")))
+        .filter_map(|entry| fs::canonicalize(entry.path()).ok())
+        .collect();
+    devices.push(device);
+    devices
+}
+
+/// Device-mapper devices (LUKS, LVM) directly on top of `device`.
+fn holders(block_root: &Path, device: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(device.join("holders")) else {
+        return Vec::new();
+    };
+
+    let mut names = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let holder = entry.file_name().to_string_lossy().into_owned();
+        if let Some(mapper) = read_string(block_root.join(&holder).join("dm/name")) {
+            names.push(format!("mapper/{mapper}"));
+        }
+        names.push(holder);
+    }
+    names
+}
+
+// The fixtures need symlinks, which Windows only allows with developer mode.
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+    use std::os::unix::fs::symlink;
+
+    /// Collects from `root/block` and `root/hwmon` without any diskstats.
+    fn collect(root: &Path) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        StorageSource::new()
+            .with_block_root(root.join("block"))
+            .with_hwmon_root(root.join("hwmon"))
+            .with_diskstats(root.join("diskstats"))
+            .collect(&mut snapshot);
+        snapshot
+    }
+
+    #[test]
+    fn matches_nvme_temperature_through_a_multipath_subsystem() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        write_tree(
+            root,
+            &[
+                ("devices/pci/nvme/nvme0/address", "0000:01:00.0\n"),
+                ("devices/nvme-subsys0/model", "Samsung SSD 980 PRO 1TB\n"),
+                ("block/nvme0n1/size", "2000409264\n"),
+                ("hwmon/hwmon1/name", "nvme\n"),
+                ("hwmon/hwmon1/temp1_input", "38850\n"),
+                ("hwmon/hwmon1/temp1_label", "Composite\n"),
+            ],
+        );
+        // nvme0n1/device -> nvme-subsys0, which lists controller nvme0.
+        symlink(root.join("devices/nvme-subsys0"), root.join("block/nvme0n1/device")).unwrap();
+        symlink(root.join("devices/pci/nvme/nvme0"), root.join("devices/nvme-subsys0/nvme0")).unwrap();
+        symlink(root.join("devices/pci/nvme/nvme0"), root.join("hwmon/hwmon1/device")).unwrap();
+
+        let snapshot = collect(root);
+        let temperature = snapshot.sensor(&"hwmon/nvme/nvme0/temp1".into()).unwrap();
+        assert_eq!(temperature.device, "nvme0n1");
+        assert_eq!(temperature.value, 38.85);
+        assert!(snapshot.alerts.is_empty());
+        assert_eq!(snapshot.drives[0].model.as_deref(), Some("Samsung SSD 980 PRO 1TB"));
+    }
+
+    #[test]
+    fn publishes_unmatched_drive_temperatures() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        write_tree(
+            root,
+            &[
+                ("devices/pci/nvme/nvme3/address", "0000:05:00.0\n"),
+                ("hwmon/hwmon1/name", "nvme\n"),
+                ("hwmon/hwmon1/temp1_input", "41000\n"),
+            ],
+        );
+        symlink(root.join("devices/pci/nvme/nvme3"), root.join("hwmon/hwmon1/device")).unwrap();
+
+        let snapshot = collect(root);
+        assert_eq!(snapshot.sensor(&"hwmon/nvme/nvme3/temp1".into()).unwrap().device, "nvme3");
+        assert!(snapshot.alerts.is_empty());
+    }
+}
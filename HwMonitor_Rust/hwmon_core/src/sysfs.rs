@@ -0,0 +1,51 @@
+//! Small helpers for reading sysfs/procfs style attribute files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Reads an attribute and trims the trailing newline; `None` if it is missing
+/// or unreadable, which for sysfs usually just means "not supported".
+pub(crate) fn read_string(path: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Reads and parses a single-value attribute.
+pub(crate) fn read_value<T: FromStr>(path: impl AsRef<Path>) -> Option<T> {
+    read_string(path)?.parse().ok()
+}
+
+/// Entries of `dir` whose file name starts with `prefix`, sorted so that
+/// `hwmon2` comes before `hwmon10`.
+pub(crate) fn numbered_entries(dir: impl AsRef<Path>, prefix: &str) -> Vec<PathBuf> {
+    indexed_entries(dir, prefix).into_iter().map(|(_, path)| path).collect()
+}
+
+/// Like [`numbered_entries`], with the number that follows `prefix`.
+pub(crate) fn indexed_entries(dir: impl AsRef<Path>, prefix: &str) -> Vec<(u32, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<(u32, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let index = name.to_str()?.strip_prefix(prefix)?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Writes `(relative path, contents)` pairs below `root`, creating directories
+/// as needed, to stand in for a sysfs tree in tests.
+#[cfg(test)]
+pub(crate) fn write_tree(root: &Path, files: &[(&str, &str)]) {
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+}
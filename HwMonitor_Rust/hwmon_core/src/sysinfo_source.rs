@@ -0,0 +1,143 @@
+use std::time::Instant;
+
+use sysinfo::{Components, Disks, System, Users};
+
+use crate::{DiskInfo, HardwareGroup, ProcessInfo, Sensor, SensorKind, SensorSource, Snapshot};
+
+/// Cross-platform source backed by the `sysinfo` crate.
+pub struct SysinfoSource {
+    system: System,
+    components: Components,
+    disks: Disks,
+    users: Users,
+    /// When processes were last refreshed, to turn their I/O counts into rates.
+    processes_refreshed: Instant,
+}
+
+impl SysinfoSource {
+    pub fn new() -> Self {
+        let mut system = System::new_all();
+        system.refresh_all(); // Initial refresh of all system information
+
+        Self {
+            system,
+            components: Components::new_with_refreshed_list(),
+            disks: Disks::new_with_refreshed_list(),
+            users: Users::new_with_refreshed_list(),
+            processes_refreshed: Instant::now(),
+        }
+    }
+
+    fn processes(&mut self) -> Vec<ProcessInfo> {
+        self.system.refresh_processes();
+        let elapsed = self.processes_refreshed.elapsed().as_secs_f64().max(f64::EPSILON);
+        self.processes_refreshed = Instant::now();
+
+        self.system
+            .processes()
+            .values()
+            // On Linux threads are listed next to their process.
+            .filter(|process| process.thread_kind().is_none())
+            .map(|process| {
+                let disk = process.disk_usage();
+                ProcessInfo {
+                    pid: process.pid().as_u32(),
+                    name: process.name().to_string(),
+                    user: process
+                        .user_id()
+                        .and_then(|uid| self.users.get_user_by_id(uid))
+                        .map(|user| user.name().to_string()),
+                    cpu_usage: f64::from(process.cpu_usage()),
+                    memory: process.memory(),
+                    disk_read: disk.read_bytes as f64 / elapsed,
+                    disk_write: disk.written_bytes as f64 / elapsed,
+                    // The task list leaves out the main thread.
+                    threads: process.tasks().map(|tasks| tasks.len() + 1),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for SysinfoSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for SysinfoSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        self.system.refresh_cpu();
+        self.disks.refresh_list();
+
+        let cpus = self.system.cpus();
+        if !cpus.is_empty() {
+            let total_load: f32 = cpus.iter().map(|cpu| cpu.cpu_usage()).sum();
+            snapshot.sensors.push(Sensor::new(
+                "sysinfo/cpu/load",
+                HardwareGroup::Cpu,
+                "cpu",
+                "CPU Load",
+                SensorKind::Load,
+                f64::from(total_load / cpus.len() as f32),
+            ));
+
+            for (i, cpu) in cpus.iter().enumerate() {
+                // Linux names CPUs after their kernel index, which can skip offline ones.
+                let name = if cpu.name().starts_with("cpu") { cpu.name().to_string() } else { format!("cpu{i}") };
+                snapshot.sensors.push(Sensor::new(
+                    format!("sysinfo/{name}/load"),
+                    HardwareGroup::Cpu,
+                    name.as_str(),
+                    format!("{} Load", name.replace("cpu", "CPU ")),
+                    SensorKind::Load,
+                    f64::from(cpu.cpu_usage()),
+                ));
+            }
+        }
+
+        // On Linux the hwmon and memory sources report the same things with more detail.
+        if cfg!(not(target_os = "linux")) {
+            self.system.refresh_memory();
+            let total = self.system.total_memory() as f64;
+            snapshot.sensors.push(
+                Sensor::new(
+                    "sysinfo/memory/used",
+                    HardwareGroup::Memory,
+                    "sysinfo",
+                    "Memory Used",
+                    SensorKind::Data,
+                    self.system.used_memory() as f64,
+                )
+                .with_max(Some(total)),
+            );
+
+            self.components.refresh_list();
+            for comp in self.components.iter().filter(|comp| comp.temperature() > 0.0) {
+                snapshot.sensors.push(
+                    Sensor::new(
+                        format!("sysinfo/component/{}", comp.label()),
+                        HardwareGroup::Board,
+                        "sysinfo",
+                        comp.label(),
+                        SensorKind::Temperature,
+                        f64::from(comp.temperature()),
+                    )
+                    .with_critical(comp.critical().map(f64::from)),
+                );
+            }
+        }
+
+        snapshot.processes = self.processes();
+
+        snapshot.disks = self.disks.iter()
+            .map(|disk| DiskInfo {
+                name: disk.name().to_string_lossy().into_owned(),
+                mount_point: disk.mount_point().to_path_buf(),
+                kind: format!("{:?}", disk.kind()),
+                total_space: disk.total_space(),
+                available_space: disk.available_space(),
+            })
+            .collect();
+    }
+}
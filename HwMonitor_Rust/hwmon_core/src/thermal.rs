@@ -0,0 +1,287 @@
+//! Thermal zones and cooling devices from `/sys/class/thermal`.
+//!
+//! A thermal zone is a temperature the firmware or a driver watches (ACPI
+//! `acpitz`, Intel `x86_pkg_temp`, ARM `cpu-thermal`), with trip points at
+//! which the kernel starts cooling: `passive` throttles, `active` turns on a
+//! fan, `critical` shuts the machine down. Cooling devices report how hard
+//! they are working as a state between 0 and `max_state`.
+
+use std::path::{Path, PathBuf};
+
+use crate::sysfs::{indexed_entries, numbered_entries, read_string, read_value};
+use crate::{Alert, HardwareGroup, Sensor, SensorKind, SensorSource, Severity, Snapshot};
+
+pub const DEFAULT_THERMAL_ROOT: &str = "/sys/class/thermal";
+
+/// A trip point of a zone, in °C.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripPoint {
+    /// `passive`, `active`, `hot` or `critical`.
+    pub kind: String,
+    pub temperature: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalZone {
+    pub index: u32,
+    /// Zone type such as `acpitz` or `x86_pkg_temp`.
+    pub kind: String,
+    pub temperature: f64,
+    pub trip_points: Vec<TripPoint>,
+    /// Whether the zone is also exported through hwmon, where
+    /// [`HwmonSource`](crate::HwmonSource) already reports its temperature.
+    /// The kernel creates one hwmon device per zone type, so only the first
+    /// zone of a type has the `hwmonN` child; the others are further `tempN`
+    /// channels of it.
+    pub has_hwmon: bool,
+}
+
+impl ThermalZone {
+    fn read(path: &Path, index: u32) -> Option<Self> {
+        let trip_points = (0..)
+            .map_while(|i| {
+                Some(TripPoint {
+                    kind: read_string(path.join(format!("trip_point_{i}_type")))?,
+                    temperature: read_value::<f64>(path.join(format!("trip_point_{i}_temp")))? / 1000.0,
+                })
+            })
+            .collect();
+
+        Some(Self {
+            index,
+            kind: read_string(path.join("type"))?,
+            temperature: read_value::<f64>(path.join("temp"))? / 1000.0,
+            trip_points,
+            has_hwmon: !numbered_entries(path, "hwmon").is_empty(),
+        })
+    }
+
+    /// The lowest trip point of `kind`; firmware sometimes sets unused ones
+    /// to 0 or absurdly high values, so only plausible ones count.
+    pub fn trip(&self, kind: &str) -> Option<f64> {
+        self.trip_points
+            .iter()
+            .filter(|trip| trip.kind == kind && trip.temperature > 0.0 && trip.temperature < 200.0)
+            .map(|trip| trip.temperature)
+            .reduce(f64::min)
+    }
+
+    pub fn group(&self) -> HardwareGroup {
+        match self.kind.as_str() {
+            "x86_pkg_temp" | "cpu-thermal" | "cpu_thermal" | "soc_thermal" => HardwareGroup::Cpu,
+            _ => HardwareGroup::Board,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoolingDevice {
+    pub index: u32,
+    /// Device type such as `Processor`, `Fan` or `intel_powerclamp`.
+    pub kind: String,
+    pub state: u64,
+    pub max_state: u64,
+}
+
+impl CoolingDevice {
+    fn read(path: &Path, index: u32) -> Option<Self> {
+        Some(Self {
+            index,
+            kind: read_string(path.join("type"))?,
+            state: read_value(path.join("cur_state"))?,
+            max_state: read_value(path.join("max_state"))?,
+        })
+    }
+
+    /// Whether cooling the device means slowing the CPU down.
+    pub fn throttles_cpu(&self) -> bool {
+        matches!(self.kind.as_str(), "Processor" | "intel_powerclamp")
+    }
+}
+
+/// Publishes zone temperatures with their trip points and how hard cooling
+/// devices are working.
+pub struct ThermalSource {
+    root: PathBuf,
+}
+
+impl ThermalSource {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_THERMAL_ROOT)
+    }
+
+    /// Reads from `root` instead of `/sys/class/thermal`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn zones(&self) -> Vec<ThermalZone> {
+        let mut zones: Vec<ThermalZone> = indexed_entries(&self.root, "thermal_zone")
+            .into_iter()
+            .filter_map(|(index, path)| ThermalZone::read(&path, index))
+            .collect();
+        let exported: Vec<String> = zones.iter().filter(|zone| zone.has_hwmon).map(|zone| zone.kind.clone()).collect();
+        for zone in &mut zones {
+            zone.has_hwmon = exported.contains(&zone.kind);
+        }
+        zones
+    }
+
+    pub fn cooling_devices(&self) -> Vec<CoolingDevice> {
+        indexed_entries(&self.root, "cooling_device")
+            .into_iter()
+            .filter_map(|(index, path)| CoolingDevice::read(&path, index))
+            .collect()
+    }
+}
+
+impl Default for ThermalSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SensorSource for ThermalSource {
+    fn collect(&mut self, snapshot: &mut Snapshot) {
+        for zone in self.zones() {
+            let passive = zone.trip("passive").or_else(|| zone.trip("hot"));
+            let critical = zone.trip("critical");
+            let label = format!("{} (zone {})", zone.kind, zone.index);
+
+            if let Some(critical) = critical.filter(|critical| zone.temperature >= *critical) {
+                snapshot.alerts.push(Alert::new(
+                    zone.group(),
+                    "thermal",
+                    Severity::Critical,
+                    format!("{label} at {:.1}°C, past its critical trip point ({critical:.0}°C)", zone.temperature),
+                ));
+            } else if let Some(passive) = passive.filter(|passive| zone.temperature >= *passive) {
+                snapshot.alerts.push(Alert::new(
+                    zone.group(),
+                    "thermal",
+                    Severity::Warning,
+                    format!("{label} at {:.1}°C, past its passive trip point ({passive:.0}°C)", zone.temperature),
+                ));
+            }
+
+            if !zone.has_hwmon {
+                snapshot.sensors.push(
+                    Sensor::new(
+                        format!("thermal/thermal_zone{}", zone.index),
+                        zone.group(),
+                        "thermal",
+                        label,
+                        SensorKind::Temperature,
+                        zone.temperature,
+                    )
+                    .with_max(passive)
+                    .with_critical(critical),
+                );
+            }
+        }
+
+        // There is one Processor device per CPU; the most throttled one stands for all of them.
+        let devices: Vec<CoolingDevice> = self.cooling_devices().into_iter().filter(|d| d.max_state > 0).collect();
+        let processor = devices
+            .iter()
+            .filter(|device| device.throttles_cpu())
+            .map(|device| device.state as f64 / device.max_state as f64 * 100.0)
+            .reduce(f64::max);
+        if let Some(level) = processor {
+            snapshot.sensors.push(Sensor::new(
+                "thermal/cooling/processor",
+                HardwareGroup::Cpu,
+                "thermal",
+                "Processor Cooling State",
+                SensorKind::Load,
+                level,
+            ));
+            if level > 0.0 {
+                snapshot.alerts.push(Alert::new(
+                    HardwareGroup::Cpu,
+                    "thermal",
+                    Severity::Warning,
+                    "Kernel is throttling the CPU through its cooling devices",
+                ));
+            }
+        }
+        for device in devices.iter().filter(|device| !device.throttles_cpu()) {
+            snapshot.sensors.push(Sensor::new(
+                format!("thermal/cooling_device{}", device.index),
+                HardwareGroup::Board,
+                "thermal",
+                format!("{} {} Cooling State", device.kind, device.index),
+                SensorKind::Load,
+                device.state as f64 / device.max_state as f64 * 100.0,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::write_tree;
+
+    #[test]
+    fn reads_zones_trip_points_and_cooling_devices() {
+        let root = tempfile::tempdir().unwrap();
+        write_tree(
+            root.path(),
+            &[
+                // Two ACPI zones share the hwmon device of the first.
+                ("thermal_zone0/type", "acpitz\n"),
+                ("thermal_zone0/temp", "27800\n"),
+                ("thermal_zone0/hwmon1/name", "acpitz\n"),
+                ("thermal_zone1/type", "acpitz\n"),
+                ("thermal_zone1/temp", "29800\n"),
+                ("thermal_zone2/type", "x86_pkg_temp\n"),
+                ("thermal_zone2/temp", "91000\n"),
+                ("thermal_zone2/trip_point_0_type", "passive\n"),
+                ("thermal_zone2/trip_point_0_temp", "90000\n"),
+                ("thermal_zone2/trip_point_1_type", "critical\n"),
+                ("thermal_zone2/trip_point_1_temp", "105000\n"),
+                ("thermal_zone2/trip_point_2_type", "active\n"),
+                ("thermal_zone2/trip_point_2_temp", "0\n"),
+                ("cooling_device0/type", "Processor\n"),
+                ("cooling_device0/cur_state", "0\n"),
+                ("cooling_device0/max_state", "10\n"),
+                ("cooling_device1/type", "Processor\n"),
+                ("cooling_device1/cur_state", "5\n"),
+                ("cooling_device1/max_state", "10\n"),
+                ("cooling_device2/type", "Fan\n"),
+                ("cooling_device2/cur_state", "1\n"),
+                ("cooling_device2/max_state", "4\n"),
+            ],
+        );
+        let mut source = ThermalSource::with_root(root.path());
+
+        let zones = source.zones();
+        assert_eq!(zones.iter().map(|zone| zone.has_hwmon).collect::<Vec<_>>(), [true, true, false]);
+        let package = &zones[2];
+        assert_eq!(package.trip_points.len(), 3);
+        assert_eq!(package.trip("passive"), Some(90.0));
+        assert_eq!(package.trip("critical"), Some(105.0));
+        // Unused trip points left at 0 do not count.
+        assert_eq!(package.trip("active"), None);
+
+        let mut snapshot = Snapshot::default();
+        source.collect(&mut snapshot);
+        let ids: Vec<&str> = snapshot.sensors.iter().map(|sensor| sensor.id.as_str()).collect();
+        assert_eq!(ids, ["thermal/thermal_zone2", "thermal/cooling/processor", "thermal/cooling_device2"]);
+        let zone = &snapshot.sensors[0];
+        assert_eq!((zone.value, zone.max, zone.critical), (91.0, Some(90.0), Some(105.0)));
+        assert_eq!(zone.group, HardwareGroup::Cpu);
+        assert_eq!(snapshot.sensors[1].value, 50.0);
+        assert_eq!(snapshot.sensors[2].value, 25.0);
+
+        let messages: Vec<&str> = snapshot.alerts.iter().map(|alert| alert.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "x86_pkg_temp (zone 2) at 91.0°C, past its passive trip point (90°C)",
+                "Kernel is throttling the CPU through its cooling devices",
+            ]
+        );
+    }
+}
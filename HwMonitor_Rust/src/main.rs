@@ -1,78 +1,616 @@
+mod processes;
+mod system_info;
+
 use eframe::egui;
-use sysinfo::{System, CpuExt, ComponentExt};
+use hwmon_core::{
+    format_bytes, inventory_history, Alert, Battery, CgroupSource, Collector, Config, CpuSource, CpuTempSelector,
+    DiskInfo, DrmGpuSource, EdacSource, FanStallDetector, HardwareGroup, HwmonSource, Inventory, InventoryAlerts,
+    InventoryChange, InventoryReader, LogicalCpu, MemorySource, NetworkSource, NvidiaSmiSource, PowerSupplySource,
+    PowercapSource, ProcStatSource, PsiSource, Sampler, Sensor, SensorKind, Severity, SmartHealth, SmartSource,
+    Snapshot, StorageSource, SysinfoSource, ThermalSource, Unit, DEFAULT_SAMPLE_INTERVAL,
+};
+
+use processes::ProcessView;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Sensors,
+    Processes,
+    SystemInfo,
+}
 
 struct HwMonitorApp {
-    system: System,
-    cpu_temp: Option<f32>,
-    cpu_load: Option<f32>,
-    // TODO: Add fields for GPU temp/load, memory temp, disk temps
+    sampler: Sampler,
+    tab: Tab,
+    processes: ProcessView,
+    inventory: Inventory,
+    /// What changed in the inventory since the last run.
+    changes: Vec<InventoryChange>,
 }
 
-impl Default for HwMonitorApp {
-    fn default() -> Self {
-        let mut sys = System::new_all();
-        sys.refresh_all(); // Initial refresh of all system information
+impl HwMonitorApp {
+    fn new(cc: &eframe::CreationContext<'_>, config: Config) -> Self {
+        // Repaints are driven by new samples rather than by a frame timer.
+        let ctx = cc.egui_ctx.clone();
+        let inventory = InventoryReader::new().read();
+        let changes = match Inventory::default_path() {
+            Some(path) => inventory.record(&path).unwrap_or_else(|e| {
+                eprintln!("{e}; not comparing the hardware inventory");
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        let source = Collector::new()
+            .with(SysinfoSource::new())
+            .with(CpuSource::new())
+            .with(ProcStatSource::new())
+            .with(ThermalSource::new())
+            .with(PsiSource::new().with_limits(config.pressure.warning, config.pressure.critical))
+            .with(CgroupSource::new())
+            .with(
+                HwmonSource::new()
+                    .skip_group(HardwareGroup::Gpu)
+                    .skip_group(HardwareGroup::Memory)
+                    .skip_group(HardwareGroup::Storage)
+                    .with_labels(config.fans.labels)
+                    .with_multipliers(config.power.multipliers),
+            )
+            .with(MemorySource::new())
+            .with(EdacSource::new())
+            .with(PowercapSource::new())
+            .with(PowerSupplySource::new())
+            .with(StorageSource::new())
+            .with(match config.storage.smartctl {
+                Some(command) => SmartSource::with_command(command),
+                None => SmartSource::new(),
+            })
+            .with(NetworkSource::new().with_filter(config.network.filter()))
+            .with(DrmGpuSource::new())
+            .with(match config.gpu.nvidia_smi {
+                Some(command) => NvidiaSmiSource::with_command(command),
+                None => NvidiaSmiSource::new(),
+            })
+            .with(CpuTempSelector::new(config.cpu.temperature_sensor))
+            .with(FanStallDetector::new(config.fans.temperatures).with_stall_temperature(config.fans.stall_temperature))
+            .with(InventoryAlerts::new(changes.clone()));
+        let sampler = Sampler::spawn(source, DEFAULT_SAMPLE_INTERVAL, move || {
+            ctx.request_repaint();
+        });
 
         Self {
-            system: sys,
-            cpu_temp: None,
-            cpu_load: None,
+            sampler,
+            tab: Tab::Sensors,
+            processes: ProcessView::default(),
+            inventory,
+            changes,
         }
     }
 }
 
 impl eframe::App for HwMonitorApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.system.refresh_cpu();
-        self.system.refresh_memory(); // Good to refresh memory info
-        self.system.refresh_components_list(); // Refresh the list of components
-        self.system.refresh_disks_list(); // Refresh the list of disks
-
-        // --- CPU Info ---
-        let cpus = self.system.cpus();
-        if !cpus.is_empty() {
-            let total_load: f32 = cpus.iter().map(|cpu| cpu.cpu_usage()).sum();
-            self.cpu_load = Some(total_load / cpus.len() as f32);
-        }
-
-        self.cpu_temp = self.system.components().iter()
-            .find(|comp| comp.label().to_lowercase().contains("cpu") && comp.temperature() > 0.0)
-            .map(|comp| comp.temperature());
-        
+        let snapshot = self.sampler.latest();
+
         // --- UI Rendering ---
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Hardware Monitor (Rust Egui)");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.tab, Tab::Sensors, "Sensors");
+                ui.selectable_value(&mut self.tab, Tab::Processes, "Processes");
+                ui.selectable_value(&mut self.tab, Tab::SystemInfo, "System info");
+            });
             ui.separator();
 
-            ui.label(format!("CPU Temperature: {:.1}°C", self.cpu_temp.unwrap_or(0.0)));
-            ui.label(format!("CPU Load: {:.1}%", self.cpu_load.unwrap_or(0.0)));
-            
-            ui.separator();
-            ui.label("GPU Temperature: N/A");
-            ui.label("GPU Load: N/A");
-            ui.label("Memory Temperature: N/A");
-            ui.separator();
-            ui.label("Disk Temperatures:");
-            
-            let disks = self.system.disks();
-            if disks.is_empty() {
-                ui.label("  No disks found.");
-            } else {
-                for disk in disks {
-                    ui.label(format!("  {}: {} (Type: {:?})", 
-                        disk.name().to_string_lossy(), 
-                        disk.mount_point().to_string_lossy(),
-                        disk.kind()
-                    ));
+            match self.tab {
+                Tab::Processes => {
+                    self.processes.show(ui, &snapshot);
+                    return;
                 }
+                Tab::SystemInfo => {
+                    system_info::show(ui, &self.inventory, &self.changes);
+                    return;
+                }
+                Tab::Sensors => {}
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for group in HardwareGroup::ALL {
+                    ui.strong(group.name());
+                    match group {
+                        HardwareGroup::Cpu => cpu_section(ui, &snapshot),
+                        HardwareGroup::Gpu => device_section(ui, &snapshot, group),
+                        HardwareGroup::Storage => storage_section(ui, &snapshot),
+                        HardwareGroup::Network => network_section(ui, &snapshot),
+                        HardwareGroup::Power => rail_section(ui, &snapshot),
+                        HardwareGroup::Battery => battery_section(ui, &snapshot),
+                        HardwareGroup::Pressure => pressure_section(ui, &snapshot),
+                        HardwareGroup::Cgroups => cgroup_section(ui, &snapshot),
+                        _ => plain_section(ui, &snapshot, group),
+                    }
+                    ui.separator();
+                }
+            });
+        });
+    }
+}
+
+/// All sensors of `group` in one list.
+fn plain_section(ui: &mut egui::Ui, snapshot: &Snapshot, group: HardwareGroup) {
+    alert_rows(ui, snapshot.alerts.iter().filter(|alert| alert.group == group), "  ", true);
+    let sensors: Vec<&Sensor> = snapshot.group(group).collect();
+    if sensors.is_empty() {
+        ui.label("  N/A");
+    } else {
+        sensor_rows(ui, &sensors, "  ");
+    }
+}
+
+/// Package-wide readings, then a grid of per-CPU load bars.
+fn cpu_section(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let group = HardwareGroup::Cpu;
+    cpu_temperature_row(ui, snapshot);
+    alert_rows(ui, snapshot.alerts.iter().filter(|alert| alert.group == group), "  ", true);
+
+    // Per-CPU readings go in the grid and the time breakdown on one line
+    // rather than the list.
+    let per_cpu: Vec<String> = snapshot.cpus.iter().map(LogicalCpu::name).collect();
+    let sensors: Vec<&Sensor> = snapshot
+        .group(group)
+        .filter(|s| !per_cpu.contains(&s.device) && !s.id.as_str().starts_with("procstat/"))
+        .collect();
+    if sensors.is_empty() && snapshot.cpus.is_empty() {
+        ui.label("  N/A");
+    }
+    sensor_rows(ui, &sensors, "  ");
+    if let Some(breakdown) = cpu_time_breakdown(snapshot, "cpu") {
+        ui.label(format!("  CPU time: {breakdown}"));
+    }
+    core_grid(ui, snapshot);
+}
+
+/// The non-idle shares of CPU time for `device` from `/proc/stat`, e.g.
+/// `user 12.0% · system 3.1% · ...`.
+fn cpu_time_breakdown(snapshot: &Snapshot, device: &str) -> Option<String> {
+    let prefix = format!("procstat/{device}/");
+    let parts: Vec<String> = snapshot
+        .sensors
+        .iter()
+        .filter_map(|sensor| Some((sensor.id.as_str().strip_prefix(&prefix)?, sensor)))
+        .filter(|(field, _)| *field != "idle")
+        .map(|(field, sensor)| format!("{field} {}", sensor.display_value()))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" · "))
+}
+
+/// One row of load bars per package, with SMT siblings framed together.
+fn core_grid(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let mut packages: Vec<u32> = snapshot.cpus.iter().map(|cpu| cpu.package).collect();
+    packages.sort();
+    packages.dedup();
+
+    for package in packages {
+        let cpus: Vec<&LogicalCpu> = snapshot.cpus.iter().filter(|cpu| cpu.package == package).collect();
+        let mut header = format!("  Package {package}");
+        if let Some(governor) = cpus.iter().find_map(|cpu| cpu.governor.as_deref()) {
+            header += &format!(": {governor}");
+        }
+        if let Some((min, max)) = cpus.iter().find_map(|cpu| cpu.min_frequency.zip(cpu.max_frequency)) {
+            header += &format!(", {min:.0}–{max:.0} MHz");
+        }
+        ui.label(header);
+
+        let mut cores: Vec<u32> = cpus.iter().map(|cpu| cpu.core).collect();
+        cores.sort();
+        cores.dedup();
+        ui.horizontal_wrapped(|ui| {
+            ui.add_space(12.0);
+            for core in cores {
+                ui.group(|ui| {
+                    ui.spacing_mut().item_spacing.x = 2.0;
+                    for cpu in cpus.iter().filter(|cpu| cpu.core == core) {
+                        core_bar(ui, snapshot, cpu, core);
+                    }
+                });
             }
-            
-            ctx.request_repaint_after(std::time::Duration::from_millis(950));
         });
     }
 }
 
+fn core_bar(ui: &mut egui::Ui, snapshot: &Snapshot, cpu: &LogicalCpu, core: u32) {
+    let name = cpu.name();
+    let load = snapshot
+        .sensors
+        .iter()
+        .find(|sensor| sensor.device == name && sensor.id.as_str().ends_with("/load"))
+        .map_or(0.0, |sensor| sensor.value);
+
+    let mut hover = format!("CPU {} (core {core}): {load:.1}%", cpu.index);
+    if let Some(frequency) = cpu.frequency {
+        hover += &format!(" at {frequency:.0} MHz");
+    }
+    if let Some(breakdown) = cpu_time_breakdown(snapshot, &name) {
+        hover += &format!("\n{breakdown}");
+    }
+    ui.add(
+        egui::ProgressBar::new((load / 100.0) as f32)
+            .desired_width(36.0)
+            .text(cpu.index.to_string()),
+    )
+    .on_hover_text(hover);
+}
+
+/// One block per device, for groups such as GPUs where a machine can have several.
+fn device_section(ui: &mut egui::Ui, snapshot: &Snapshot, group: HardwareGroup) {
+    let sensors: Vec<&Sensor> = snapshot.group(group).collect();
+    let unattached = snapshot.alerts.iter().filter(|alert| {
+        alert.group == group && !sensors.iter().any(|sensor| sensor.device == alert.device)
+    });
+    alert_rows(ui, unattached, "  ", true);
+
+    if sensors.is_empty() {
+        ui.label("  N/A");
+    }
+    for (device, sensors) in by_device(&sensors) {
+        ui.label(format!("  {device}"));
+        alert_rows(ui, snapshot.alerts_for(group, device), "    ", false);
+        sensor_rows(ui, &sensors, "    ");
+    }
+}
+
+/// Physical drives with their temperatures and the filesystems mounted from them.
+fn storage_section(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let group = HardwareGroup::Storage;
+    let unattached = snapshot.alerts.iter().filter(|alert| {
+        alert.group == group && !snapshot.drives.iter().any(|drive| drive.name == alert.device)
+    });
+    alert_rows(ui, unattached, "  ", true);
+    // Temperature sensors of drives that were not found, e.g. a controller
+    // without namespaces.
+    let unmatched: Vec<&Sensor> = snapshot.group(group)
+        .filter(|s| s.kind == SensorKind::Temperature && !snapshot.drives.iter().any(|drive| drive.name == s.device))
+        .collect();
+    sensor_rows(ui, &unmatched, "  ");
+
+    if snapshot.drives.is_empty() && snapshot.disks.is_empty() {
+        ui.label("  No disks found.");
+        return;
+    }
+
+    for drive in &snapshot.drives {
+        let model = drive.model.as_deref().unwrap_or("Unknown model");
+        ui.label(format!("  {}: {} ({}, {})",
+            drive.name,
+            model,
+            drive.kind.name(),
+            format_bytes(drive.size as f64)
+        ));
+        if let Some(health) = &drive.smart {
+            smart_row(ui, health);
+        }
+        alert_rows(ui, snapshot.alerts_for(group, &drive.name), "    ", false);
+        let sensors: Vec<&Sensor> = snapshot.group(group).filter(|s| s.device == drive.name).collect();
+        if !sensors.iter().any(|s| s.kind == SensorKind::Temperature) {
+            ui.label("    No temperature sensor");
+        }
+        sensor_rows(ui, &sensors, "    ");
+        for disk in snapshot.disks.iter().filter(|disk| drive.holds(disk)) {
+            mount_row(ui, disk, &format!("    {} on {}", disk.mount_point.display(), disk.name));
+        }
+    }
+
+    let others: Vec<&DiskInfo> = snapshot.disks.iter()
+        .filter(|disk| !snapshot.drives.iter().any(|drive| drive.holds(disk)))
+        .collect();
+    if !others.is_empty() {
+        ui.label(if snapshot.drives.is_empty() { "  Mounts" } else { "  Other mounts" });
+        for disk in others {
+            mount_row(ui, disk, &format!("    {}: {} (Type: {})",
+                disk.name,
+                disk.mount_point.display(),
+                disk.kind
+            ));
+        }
+    }
+}
+
+/// A mount with a bar showing how full it is.
+fn mount_row(ui: &mut egui::Ui, disk: &DiskInfo, text: &str) {
+    ui.horizontal(|ui| {
+        ui.label(text);
+        if disk.total_space > 0 {
+            let used = disk.used_space() as f64 / disk.total_space as f64;
+            ui.add(
+                egui::ProgressBar::new(used as f32)
+                    .desired_width(180.0)
+                    .text(format!("{} free of {}", format_bytes(disk.available_space as f64), format_bytes(disk.total_space as f64))),
+            );
+        }
+    });
+}
+
+/// One block per interface with its link state and traffic.
+fn network_section(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let group = HardwareGroup::Network;
+    if snapshot.interfaces.is_empty() {
+        ui.label("  No interfaces found.");
+        return;
+    }
+
+    for interface in &snapshot.interfaces {
+        let mut details = vec![interface.operstate.clone()];
+        if let Some(speed) = interface.speed {
+            details.push(format!("{speed} Mb/s"));
+        }
+        if let Some(mtu) = interface.mtu {
+            details.push(format!("MTU {mtu}"));
+        }
+        ui.label(format!("  {}: {}", interface.name, details.join(", ")));
+        alert_rows(ui, snapshot.alerts_for(group, &interface.name), "    ", false);
+
+        // Error and drop rates are only worth a row while they are non-zero.
+        let sensors: Vec<&Sensor> = snapshot
+            .group(group)
+            .filter(|s| s.device == interface.name)
+            .filter(|s| s.value > 0.0 || !(s.id.as_str().ends_with("_errors") || s.id.as_str().ends_with("_dropped")))
+            .collect();
+        sensor_rows(ui, &sensors, "    ");
+    }
+}
+
+/// Voltage, current and power rails per chip, with their limits; rails
+/// outside them are drawn in red.
+fn rail_section(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let group = HardwareGroup::Power;
+    let sensors: Vec<&Sensor> = snapshot.group(group).collect();
+    if sensors.is_empty() {
+        ui.label("  N/A");
+    }
+    for (device, sensors) in by_device(&sensors) {
+        ui.label(format!("  {device}"));
+        alert_rows(ui, snapshot.alerts_for(group, device), "    ", false);
+        for sensor in sensors {
+            let limits = match (sensor.min, sensor.max) {
+                (Some(min), Some(max)) => format!(" ({} – {})", sensor.unit.format(min), sensor.unit.format(max)),
+                (Some(min), None) => format!(" (min {})", sensor.unit.format(min)),
+                (None, Some(max)) => format!(" (max {})", sensor.unit.format(max)),
+                (None, None) => String::new(),
+            };
+            let text = format!("    {}: {}{limits}", sensor.label, sensor.display_value());
+            if sensor.out_of_range() {
+                ui.colored_label(egui::Color32::from_rgb(220, 50, 50), text);
+            } else {
+                ui.label(text);
+            }
+        }
+    }
+}
+
+/// Adapter state, then one block per battery with its status and estimates.
+fn battery_section(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let group = HardwareGroup::Battery;
+    match snapshot.ac_online {
+        Some(true) => ui.label("  AC adapter: online"),
+        Some(false) => ui.label("  AC adapter: offline"),
+        None => ui.label("  AC adapter: N/A"),
+    };
+    if snapshot.batteries.is_empty() {
+        ui.label("  No battery");
+        return;
+    }
+
+    for battery in &snapshot.batteries {
+        ui.label(format!("  {}: {}", battery.name, battery_summary(battery)));
+        alert_rows(ui, snapshot.alerts_for(group, &battery.name), "    ", false);
+        let sensors: Vec<&Sensor> = snapshot.group(group).filter(|s| s.device == battery.name).collect();
+        sensor_rows(ui, &sensors, "    ");
+    }
+}
+
+/// e.g. `Discharging, 2 h 15 min to empty, 312 cycles (DELL 7FJ9271)`.
+fn battery_summary(battery: &Battery) -> String {
+    let mut summary = battery.status.clone();
+    if let Some(remaining) = battery.time_remaining() {
+        let minutes = remaining.as_secs() / 60;
+        let goal = if battery.status == "Charging" { "full" } else { "empty" };
+        summary += &format!(", {} h {} min to {goal}", minutes / 60, minutes % 60);
+    }
+    if let Some(cycles) = battery.cycle_count {
+        summary += &format!(", {cycles} cycles");
+    }
+    if let Some(model) = &battery.model {
+        summary += &format!(" ({model})");
+    }
+    summary
+}
+
+/// Stall averages as a table, one row per resource and `some`/`full` line.
+fn pressure_section(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let group = HardwareGroup::Pressure;
+    alert_rows(ui, snapshot.alerts.iter().filter(|alert| alert.group == group), "  ", true);
+    let sensors: Vec<&Sensor> = snapshot.group(group).collect();
+    if sensors.is_empty() {
+        ui.label("  N/A");
+        return;
+    }
+
+    egui::Grid::new("pressure").striped(true).show(ui, |ui| {
+        for heading in ["", "avg10", "avg60", "avg300", "last sample", "stall time"] {
+            ui.weak(heading);
+        }
+        ui.end_row();
+        for (resource, sensors) in by_device(&sensors) {
+            for line in ["some", "full"] {
+                let field = |field: &str| {
+                    let suffix = format!("/{line}/{field}");
+                    sensors.iter().find(|sensor| sensor.id.as_str().ends_with(&suffix))
+                };
+                if field("avg10").is_none() {
+                    continue;
+                }
+                ui.label(format!("  {resource} {line}"));
+                for name in ["avg10", "avg60", "avg300", "stalled", "stall_time"] {
+                    ui.label(field(name).map_or("–".to_string(), |sensor| sensor.display_value()));
+                }
+                ui.end_row();
+            }
+        }
+    });
+}
+
+/// One table row per systemd unit or container, busiest first.
+fn cgroup_section(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    let group = HardwareGroup::Cgroups;
+    if snapshot.cgroups.is_empty() {
+        ui.label("  N/A");
+        return;
+    }
+
+    let sensors: Vec<&Sensor> = snapshot.group(group).collect();
+    let field = |path: &str, field: &str| {
+        let id = format!("cgroup/{path}/{field}");
+        sensors.iter().find(|sensor| sensor.id.as_str() == id).copied()
+    };
+    let mut cgroups: Vec<_> = snapshot.cgroups.iter().collect();
+    let cpu = |path: &str| field(path, "cpu").map_or(0.0, |sensor| sensor.value);
+    cgroups.sort_by(|a, b| cpu(&b.path).total_cmp(&cpu(&a.path)));
+
+    egui::Grid::new("cgroups").striped(true).show(ui, |ui| {
+        for heading in ["", "CPU", "Memory", "Read", "Write", "PIDs", "PSI cpu/mem/io"] {
+            ui.weak(heading);
+        }
+        ui.end_row();
+        for cgroup in cgroups {
+            let value = |name: &str| field(&cgroup.path, name).map_or("–".to_string(), |sensor| sensor.display_value());
+            ui.label(format!("  {}", cgroup.name)).on_hover_text(&cgroup.path);
+            ui.label(value("cpu"));
+            ui.label(match field(&cgroup.path, "memory") {
+                Some(memory) => match memory.max {
+                    Some(limit) => format!("{} / {}", memory.display_value(), format_bytes(limit)),
+                    None => memory.display_value(),
+                },
+                None => "–".to_string(),
+            });
+            ui.label(value("io_read"));
+            ui.label(value("io_write"));
+            ui.label(cgroup.pids.map_or("–".to_string(), |pids| pids.to_string()));
+            ui.label(format!("{} / {} / {}", value("pressure_cpu"), value("pressure_memory"), value("pressure_io")));
+            ui.end_row();
+        }
+    });
+}
+
+/// The drive's own verdict; the SMART counters are sensors of the drive.
+fn smart_row(ui: &mut egui::Ui, health: &SmartHealth) {
+    match health.passed {
+        Some(true) => ui.label("    SMART: PASSED"),
+        Some(false) => ui.colored_label(egui::Color32::from_rgb(220, 50, 50), "    SMART: FAILED"),
+        None => return,
+    };
+}
+
+fn severity_color(ui: &egui::Ui, severity: Severity) -> egui::Color32 {
+    match severity {
+        Severity::Info => ui.visuals().weak_text_color(),
+        Severity::Warning => egui::Color32::from_rgb(230, 170, 0),
+        Severity::Critical => egui::Color32::from_rgb(220, 50, 50),
+    }
+}
+
+fn alert_rows<'a>(ui: &mut egui::Ui, alerts: impl Iterator<Item = &'a Alert>, indent: &str, show_device: bool) {
+    for alert in alerts {
+        let color = severity_color(ui, alert.severity);
+        let text = if show_device {
+            format!("{}: {}", alert.device, alert.message)
+        } else {
+            alert.message.clone()
+        };
+        ui.colored_label(color, format!("{indent}⚠ {text}"));
+    }
+}
+
+fn sensor_rows(ui: &mut egui::Ui, sensors: &[&Sensor], indent: &str) {
+    for sensor in sensors {
+        let value = match (sensor.unit, sensor.max) {
+            (Unit::Bytes | Unit::WattHours, Some(total)) => format!("{} / {}", sensor.display_value(), sensor.unit.format(total)),
+            _ => sensor.display_value(),
+        };
+        ui.label(format!("{indent}{}: {value}", sensor.label));
+    }
+}
+
+/// Splits sensors into per-device blocks, keeping the order devices first appear in.
+fn by_device<'a>(sensors: &[&'a Sensor]) -> Vec<(&'a str, Vec<&'a Sensor>)> {
+    let mut devices: Vec<(&str, Vec<&Sensor>)> = Vec::new();
+    for sensor in sensors {
+        match devices.iter_mut().find(|(device, _)| *device == sensor.device) {
+            Some((_, list)) => list.push(sensor),
+            None => devices.push((&sensor.device, vec![sensor])),
+        }
+    }
+    devices
+}
+
+fn cpu_temperature_row(ui: &mut egui::Ui, snapshot: &Snapshot) {
+    ui.horizontal(|ui| {
+        match (snapshot.cpu_temperature(), &snapshot.cpu_temperature) {
+            (Some(sensor), Some(selection)) => {
+                ui.label(format!("  CPU Temperature: {}", sensor.display_value()));
+                ui.weak(format!("{} ({})", selection.reason, sensor.id))
+                    .on_hover_text("Pin a different sensor with cpu.temperature_sensor in the config file");
+            }
+            _ => {
+                ui.label("  CPU Temperature: N/A");
+            }
+        }
+        if cpu_throttling(snapshot) {
+            ui.colored_label(egui::Color32::from_rgb(220, 50, 50), "THROTTLING");
+        }
+    });
+}
+
+/// Whether throttle counters went up or the kernel is holding the CPU back
+/// through its cooling devices.
+fn cpu_throttling(snapshot: &Snapshot) -> bool {
+    snapshot.alerts_for(HardwareGroup::Cpu, "thermal_throttle").next().is_some()
+        || snapshot
+            .sensor(&"thermal/cooling/processor".into())
+            .is_some_and(|sensor| sensor.value > 0.0)
+}
+
 fn main() -> Result<(), eframe::Error> {
+    // `--inventory [json|markdown]` prints the hardware inventory and
+    // `--inventory-diff OLD NEW` the changes between two saved ones.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("--inventory") => {
+            let inventory = InventoryReader::new().read();
+            match args.get(1).map(String::as_str) {
+                Some("markdown" | "md") => print!("{}", inventory.to_markdown()),
+                _ => println!("{}", inventory.to_json()),
+            }
+            return Ok(());
+        }
+        Some("--inventory-diff") => {
+            let [old, new] = [1, 2].map(|i| args.get(i).map(|path| Inventory::load(path.as_ref())));
+            match (old, new) {
+                (Some(Ok(old)), Some(Ok(new))) => {
+                    for change in inventory_history::diff(&old, &new) {
+                        println!("{change}");
+                    }
+                }
+                (Some(Err(e)), _) | (_, Some(Err(e))) => eprintln!("{e}"),
+                _ => eprintln!("usage: --inventory-diff OLD.json NEW.json"),
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let config = Config::load_default().unwrap_or_else(|e| {
+        eprintln!("{e}; using default settings");
+        Config::default()
+    });
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([380.0, 500.0]),
         ..Default::default()
@@ -80,6 +618,6 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Hardware Monitor App",
         options,
-        Box::new(|_cc| Box::<HwMonitorApp>::default()),
+        Box::new(|cc| Box::new(HwMonitorApp::new(cc, config))),
     )
-} 
\ No newline at end of file
+}
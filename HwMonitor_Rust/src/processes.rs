@@ -0,0 +1,143 @@
+//! The Processes tab: a sortable, filterable table of the processes in the
+//! latest snapshot.
+
+use std::cmp::Ordering;
+
+use eframe::egui;
+use hwmon_core::{format_bytes, ProcessInfo, Snapshot};
+
+/// Rows drawn at most; the rest are one sort or filter away.
+const MAX_ROWS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Pid,
+    Name,
+    User,
+    Cpu,
+    Memory,
+    DiskRead,
+    DiskWrite,
+    Threads,
+}
+
+impl Column {
+    const ALL: [Column; 8] = [
+        Column::Pid,
+        Column::Name,
+        Column::User,
+        Column::Cpu,
+        Column::Memory,
+        Column::DiskRead,
+        Column::DiskWrite,
+        Column::Threads,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::Name => "Name",
+            Column::User => "User",
+            Column::Cpu => "CPU",
+            Column::Memory => "Memory",
+            Column::DiskRead => "Read",
+            Column::DiskWrite => "Write",
+            Column::Threads => "Threads",
+        }
+    }
+
+    fn compare(self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        match self {
+            Column::Pid => a.pid.cmp(&b.pid),
+            Column::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            Column::User => a.user.cmp(&b.user),
+            Column::Cpu => a.cpu_usage.total_cmp(&b.cpu_usage),
+            Column::Memory => a.memory.cmp(&b.memory),
+            Column::DiskRead => a.disk_read.total_cmp(&b.disk_read),
+            Column::DiskWrite => a.disk_write.total_cmp(&b.disk_write),
+            Column::Threads => a.threads.cmp(&b.threads),
+        }
+    }
+
+    /// Numbers are most interesting largest first, text A to Z.
+    fn descending_by_default(self) -> bool {
+        !matches!(self, Column::Pid | Column::Name | Column::User)
+    }
+}
+
+/// Sort order and filter, kept across frames.
+pub struct ProcessView {
+    sort: Column,
+    descending: bool,
+    filter: String,
+}
+
+impl Default for ProcessView {
+    fn default() -> Self {
+        Self {
+            sort: Column::Cpu,
+            descending: true,
+            filter: String::new(),
+        }
+    }
+}
+
+impl ProcessView {
+    pub fn show(&mut self, ui: &mut egui::Ui, snapshot: &Snapshot) {
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+
+        let filter = self.filter.to_lowercase();
+        let mut processes: Vec<&ProcessInfo> = snapshot
+            .processes
+            .iter()
+            .filter(|process| process.name.to_lowercase().contains(&filter))
+            .collect();
+        processes.sort_by(|a, b| {
+            let order = self.sort.compare(a, b);
+            if self.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+
+        if processes.len() > MAX_ROWS {
+            ui.weak(format!("Showing {MAX_ROWS} of {} processes", processes.len()));
+        }
+
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("processes").striped(true).show(ui, |ui| {
+                for column in Column::ALL {
+                    let mut title = column.title().to_string();
+                    if column == self.sort {
+                        title += if self.descending { " ⏷" } else { " ⏶" };
+                    }
+                    if ui.selectable_label(column == self.sort, title).clicked() {
+                        if column == self.sort {
+                            self.descending = !self.descending;
+                        } else {
+                            self.sort = column;
+                            self.descending = column.descending_by_default();
+                        }
+                    }
+                }
+                ui.end_row();
+
+                for process in processes.iter().take(MAX_ROWS) {
+                    ui.label(process.pid.to_string());
+                    ui.label(&process.name);
+                    ui.label(process.user.as_deref().unwrap_or("?"));
+                    ui.label(format!("{:.1}%", process.cpu_usage));
+                    ui.label(format_bytes(process.memory as f64));
+                    ui.label(format!("{}/s", format_bytes(process.disk_read)));
+                    ui.label(format!("{}/s", format_bytes(process.disk_write)));
+                    ui.label(process.threads.map_or("?".to_string(), |threads| threads.to_string()));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
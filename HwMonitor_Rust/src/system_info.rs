@@ -0,0 +1,97 @@
+//! The System info tab: the hardware inventory read at startup, what changed
+//! since the last run, and buttons to copy it for a support ticket.
+
+use eframe::egui;
+use hwmon_core::{format_bytes, Inventory, InventoryChange};
+
+use crate::severity_color;
+
+fn row(ui: &mut egui::Ui, name: &str, value: Option<&str>) {
+    ui.label(name);
+    ui.label(value.unwrap_or("–"));
+    ui.end_row();
+}
+
+pub fn show(ui: &mut egui::Ui, inventory: &Inventory, changes: &[InventoryChange]) {
+    ui.horizontal(|ui| {
+        if ui.button("Copy as JSON").clicked() {
+            ui.output_mut(|output| output.copied_text = inventory.to_json());
+        }
+        if ui.button("Copy as Markdown").clicked() {
+            ui.output_mut(|output| output.copied_text = inventory.to_markdown());
+        }
+    });
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        if !changes.is_empty() {
+            ui.strong("Changed since the last run");
+            for change in changes {
+                ui.colored_label(severity_color(ui, change.severity), format!("  {change}"));
+            }
+            ui.separator();
+        }
+
+        ui.strong("System");
+        egui::Grid::new("inventory_system").num_columns(2).show(ui, |ui| {
+            let system = &inventory.system;
+            row(ui, "Vendor", system.vendor.as_deref());
+            row(ui, "Product", system.product.as_deref());
+            row(ui, "Board", system.board.as_deref());
+            row(ui, "Board vendor", system.board_vendor.as_deref());
+            row(ui, "BIOS", system.bios_version.as_deref());
+            row(ui, "BIOS date", system.bios_date.as_deref());
+            row(ui, "Kernel", inventory.kernel.as_deref());
+        });
+        ui.separator();
+
+        ui.strong("CPU");
+        egui::Grid::new("inventory_cpu").num_columns(2).show(ui, |ui| {
+            let cpu = &inventory.cpu;
+            row(ui, "Model", cpu.model.as_deref());
+            row(ui, "Microcode", cpu.microcode.as_deref());
+            let topology = format!("{} packages, {} cores, {} threads", cpu.packages, cpu.cores, cpu.threads);
+            row(ui, "Topology", Some(&topology));
+        });
+        ui.collapsing(format!("{} flags", inventory.cpu.flags.len()), |ui| {
+            ui.label(inventory.cpu.flags.join(" "));
+        });
+        ui.separator();
+
+        ui.strong("Memory");
+        ui.label(format_bytes(inventory.memory as f64));
+        for dimm in &inventory.dimms {
+            let kind = dimm.kind.as_deref().unwrap_or("");
+            let size = dimm.size.map(|size| format_bytes(size as f64)).unwrap_or_default();
+            ui.label(format!("  {} {size} {kind}", dimm.label));
+        }
+        ui.separator();
+
+        ui.strong("GPUs");
+        for gpu in &inventory.gpus {
+            ui.label(format!(
+                "{} {} {} ({})",
+                gpu.address,
+                gpu.vendor,
+                gpu.device_id.as_deref().unwrap_or(""),
+                gpu.driver.as_deref().unwrap_or("no driver")
+            ));
+        }
+        ui.separator();
+
+        ui.strong("Disks");
+        egui::Grid::new("inventory_disks").striped(true).show(ui, |ui| {
+            for title in ["Name", "Model", "Serial", "Firmware", "Size"] {
+                ui.strong(title);
+            }
+            ui.end_row();
+            for disk in &inventory.disks {
+                ui.label(&disk.name);
+                ui.label(disk.model.as_deref().unwrap_or("–"));
+                ui.label(disk.serial.as_deref().unwrap_or("–"));
+                ui.label(disk.firmware.as_deref().unwrap_or("–"));
+                ui.label(format!("{} {}", format_bytes(disk.size as f64), disk.kind));
+                ui.end_row();
+            }
+        });
+    });
+}